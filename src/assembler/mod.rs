@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use crate::processor::PROGRAM_START;
+
+/// Errors that can happen while assembling. Lines are counted from 1
+#[derive(Debug, PartialEq)]
+pub enum AsmError {
+    /// The mnemonic isn't one the assembler knows
+    UnknownMnemonic { line: usize, mnemonic: String },
+
+    /// The operands don't fit the mnemonic, e.g. there are too few of them or a register is
+    /// given where a number is expected
+    BadOperands { line: usize },
+
+    /// A number doesn't fit the field it's encoded in
+    OutOfRange { line: usize, value: u32 },
+
+    /// A label is used but never defined
+    UnknownLabel { line: usize, label: String },
+
+    /// A label is defined more than once
+    DuplicateLabel { line: usize, label: String }
+}
+
+impl std::fmt::Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic { line, mnemonic } => write!(f, "line {}: unknown mnemonic {}", line, mnemonic),
+            AsmError::BadOperands { line } => write!(f, "line {}: bad operands", line),
+            AsmError::OutOfRange { line, value } => write!(f, "line {}: {:#X} is out of range", line, value),
+            AsmError::UnknownLabel { line, label } => write!(f, "line {}: unknown label {}", line, label),
+            AsmError::DuplicateLabel { line, label } => write!(f, "line {}: label {} is already defined", line, label)
+        }
+    }
+}
+
+/// An operand after its kind is worked out
+#[derive(Clone, Copy, PartialEq)]
+enum Operand<'a> {
+    /// Vx
+    Register(u16),
+
+    /// A number or a label, resolved to a number in the second pass
+    Value(&'a str),
+
+    /// I, [I], DT, ST, K, F, HF, B or R
+    Special(&'static str),
+
+    /// `long NNNN`, the 16 bit address of an XO-CHIP long load
+    Long(&'a str)
+}
+
+const SPECIALS: [&str; 9] = ["I", "[I]", "DT", "ST", "K", "F", "HF", "B", "R"];
+
+const MNEMONICS: [&str; 30] = [
+    "CLS", "RET", "SCR", "SCL", "EXIT", "LOW", "HIGH", "AUDIO", "SCD", "JP", "CALL", "SE", "SNE", "LD",
+    "ADD", "OR", "AND", "XOR", "SUB", "SHR", "SUBN", "SHL", "RND", "DRW", "SKP", "SKNP", "PLANE", "PITCH",
+    "DW", "DB",
+];
+
+/// Assembles chip-8 source into a ROM loaded at `PROGRAM_START`.
+///
+/// The syntax is the one `disassemble_opcode` produces, so disassembled ROMs assemble back to
+/// the same bytes. Every line holds an optional `label:`, an optional instruction and an optional
+/// `; comment`. Numbers are decimal, or hex and binary with a `0x` or `0b` prefix, and labels can
+/// be used wherever an address or number is expected. `DW` and `DB` emit raw words and bytes.
+/// `LD I, long NNNN` is the four byte XO-CHIP long load
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut instructions = Vec::new();
+    let mut addr = PROGRAM_START;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut code = line.split(';').next().unwrap_or("").trim();
+
+        if let Some(colon) = code.find(':') {
+            let label = code[..colon].trim();
+            if labels.insert(label.to_ascii_uppercase(), addr).is_some() {
+                return Err(AsmError::DuplicateLabel { line: line_number, label: label.to_string() });
+            }
+            code = code[colon + 1..].trim();
+        }
+
+        if code.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, operands) = match code.find(char::is_whitespace) {
+            Some(space) => (&code[..space], code[space..].trim()),
+            None => (code, "")
+        };
+        let mnemonic = mnemonic.to_ascii_uppercase();
+        if !MNEMONICS.contains(&mnemonic.as_str()) {
+            return Err(AsmError::UnknownMnemonic { line: line_number, mnemonic });
+        }
+
+        addr += match mnemonic.as_str() {
+            "DB" => 1,
+            "LD" if operands.split(',').any(|operand| matches!(parse_operand(operand.trim()), Operand::Long(_))) => 4,
+            _ => 2
+        };
+        instructions.push((line_number, mnemonic, operands));
+    }
+
+    let mut rom = Vec::new();
+
+    for (line, mnemonic, operands) in instructions {
+        let operands: Vec<Operand> = if operands.is_empty() {
+            Vec::new()
+        }
+        else {
+            operands.split(',').map(|operand| parse_operand(operand.trim())).collect()
+        };
+        let value = |text: &str, max: u32| resolve(text, max, line, &labels);
+
+        if mnemonic == "DB" {
+            match operands[..] {
+                [Operand::Value(byte)] => rom.push(value(byte, 0xFF)? as u8),
+                _ => return Err(AsmError::BadOperands { line })
+            }
+            continue;
+        }
+        if let ("LD", [Operand::Special("I"), Operand::Long(addr)]) = (mnemonic.as_str(), &operands[..]) {
+            rom.extend_from_slice(&0xF000u16.to_be_bytes());
+            rom.extend_from_slice(&(value(addr, 0xFFFF)? as u16).to_be_bytes());
+            continue;
+        }
+
+        let opcode = encode(&mnemonic, &operands, line, value)?;
+        rom.extend_from_slice(&opcode.to_be_bytes());
+    }
+
+    Ok(rom)
+}
+
+/// Encodes one instruction, checking the operands fit the mnemonic
+fn encode<'a>(
+    mnemonic: &str,
+    operands: &[Operand<'a>],
+    line: usize,
+    value: impl Fn(&'a str, u32) -> Result<u32, AsmError>
+) -> Result<u16, AsmError> {
+    use Operand::{Register as V, Special as S, Value as N};
+
+    let with_value = |opcode: u16, text: &'a str, max: u32| value(text, max).map(|n| opcode | n as u16);
+    let xy = |opcode: u16, x: u16, y: u16| Ok(opcode | x << 8 | y << 4);
+
+    match (mnemonic, operands) {
+        ("CLS", []) => Ok(0x00E0),
+        ("RET", []) => Ok(0x00EE),
+        ("SCR", []) => Ok(0x00FB),
+        ("SCL", []) => Ok(0x00FC),
+        ("EXIT", []) => Ok(0x00FD),
+        ("LOW", []) => Ok(0x00FE),
+        ("HIGH", []) => Ok(0x00FF),
+        ("AUDIO", []) => Ok(0xF002),
+        ("SCD", [N(n)]) => with_value(0x00C0, n, 0xF),
+        ("JP", [N(addr)]) => with_value(0x1000, addr, 0xFFF),
+        ("JP", [V(0), N(addr)]) => with_value(0xB000, addr, 0xFFF),
+        ("CALL", [N(addr)]) => with_value(0x2000, addr, 0xFFF),
+        ("SE", [V(x), N(kk)]) => with_value(0x3000 | x << 8, kk, 0xFF),
+        ("SE", [V(x), V(y)]) => xy(0x5000, *x, *y),
+        ("SNE", [V(x), N(kk)]) => with_value(0x4000 | x << 8, kk, 0xFF),
+        ("SNE", [V(x), V(y)]) => xy(0x9000, *x, *y),
+        ("LD", [V(x), N(kk)]) => with_value(0x6000 | x << 8, kk, 0xFF),
+        ("LD", [V(x), V(y)]) => xy(0x8000, *x, *y),
+        ("LD", [S("I"), N(addr)]) => with_value(0xA000, addr, 0xFFF),
+        ("LD", [V(x), S("DT")]) => xy(0xF007, *x, 0),
+        ("LD", [V(x), S("K")]) => xy(0xF00A, *x, 0),
+        ("LD", [S("DT"), V(x)]) => xy(0xF015, *x, 0),
+        ("LD", [S("ST"), V(x)]) => xy(0xF018, *x, 0),
+        ("LD", [S("F"), V(x)]) => xy(0xF029, *x, 0),
+        ("LD", [S("HF"), V(x)]) => xy(0xF030, *x, 0),
+        ("LD", [S("B"), V(x)]) => xy(0xF033, *x, 0),
+        ("LD", [S("[I]"), V(x)]) => xy(0xF055, *x, 0),
+        ("LD", [V(x), S("[I]")]) => xy(0xF065, *x, 0),
+        ("LD", [S("R"), V(x)]) => xy(0xF075, *x, 0),
+        ("LD", [V(x), S("R")]) => xy(0xF085, *x, 0),
+        ("ADD", [V(x), N(kk)]) => with_value(0x7000 | x << 8, kk, 0xFF),
+        ("ADD", [V(x), V(y)]) => xy(0x8004, *x, *y),
+        ("ADD", [S("I"), V(x)]) => xy(0xF01E, *x, 0),
+        ("OR", [V(x), V(y)]) => xy(0x8001, *x, *y),
+        ("AND", [V(x), V(y)]) => xy(0x8002, *x, *y),
+        ("XOR", [V(x), V(y)]) => xy(0x8003, *x, *y),
+        ("SUB", [V(x), V(y)]) => xy(0x8005, *x, *y),
+        ("SHR", [V(x), V(y)]) => xy(0x8006, *x, *y),
+        ("SHR", [V(x)]) => xy(0x8006, *x, *x),
+        ("SUBN", [V(x), V(y)]) => xy(0x8007, *x, *y),
+        ("SHL", [V(x), V(y)]) => xy(0x800E, *x, *y),
+        ("SHL", [V(x)]) => xy(0x800E, *x, *x),
+        ("RND", [V(x), N(kk)]) => with_value(0xC000 | x << 8, kk, 0xFF),
+        ("DRW", [V(x), V(y), N(n)]) => with_value(0xD000 | x << 8 | y << 4, n, 0xF),
+        ("SKP", [V(x)]) => xy(0xE09E, *x, 0),
+        ("SKNP", [V(x)]) => xy(0xE0A1, *x, 0),
+        ("PLANE", [N(n)]) => value(n, 0xF).map(|n| 0xF001 | (n as u16) << 8),
+        ("PITCH", [V(x)]) => xy(0xF03A, *x, 0),
+        ("DW", [N(word)]) => with_value(0x0000, word, 0xFFFF),
+        _ => Err(AsmError::BadOperands { line })
+    }
+}
+
+fn parse_operand(text: &str) -> Operand<'_> {
+    let upper = text.to_ascii_uppercase();
+
+    if let Some(special) = SPECIALS.iter().find(|&&special| special == upper) {
+        return Operand::Special(special);
+    }
+    if let Some(addr) = upper.strip_prefix("LONG ") {
+        return Operand::Long(text[text.len() - addr.len()..].trim());
+    }
+    if upper.len() == 2 && upper.starts_with('V') {
+        if let Some(x) = upper[1..].chars().next().and_then(|c| c.to_digit(16)) {
+            return Operand::Register(x as u16);
+        }
+    }
+
+    Operand::Value(text)
+}
+
+/// Turns a number or label into its value, checking that it's at most `max`
+fn resolve(text: &str, max: u32, line: usize, labels: &HashMap<String, usize>) -> Result<u32, AsmError> {
+    let lower = text.to_ascii_lowercase();
+    let number = if let Some(hex) = lower.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16).ok()
+    }
+    else if let Some(binary) = lower.strip_prefix("0b") {
+        u32::from_str_radix(binary, 2).ok()
+    }
+    else if lower.starts_with(|c: char| c.is_ascii_digit()) {
+        lower.parse().ok()
+    }
+    else {
+        None
+    };
+
+    let value = match number {
+        Some(number) => number,
+        None if text.is_empty() || lower.starts_with(|c: char| c.is_ascii_digit()) => {
+            return Err(AsmError::BadOperands { line });
+        }
+        None => match labels.get(&text.to_ascii_uppercase()) {
+            Some(&addr) => addr as u32,
+            None => return Err(AsmError::UnknownLabel { line, label: text.to_string() })
+        }
+    };
+
+    if value > max {
+        return Err(AsmError::OutOfRange { line, value });
+    }
+
+    Ok(value)
+}
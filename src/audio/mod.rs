@@ -1,27 +1,97 @@
 use sdl2::audio::{AudioDevice, AudioCallback, AudioSpecDesired};
 
-/// https://github.com/starrhorne/chip8-rust/blob/master/src/drivers/audio_driver.rs
+// https://github.com/starrhorne/chip8-rust/blob/master/src/drivers/audio_driver.rs
 
+/// Volume the beep is played at unless set otherwise
+pub const DEFAULT_VOLUME: f32 = 0.25;
 
-pub struct SquareWave {
+/// Pitch of the beep in Hz unless set otherwise
+pub const DEFAULT_FREQUENCY: f32 = 240.0;
+
+/// Lowest and highest pitch in Hz the beep can be set to
+pub const FREQUENCY_RANGE: (f32, f32) = (20.0, 20000.0);
+
+/// Shape of the beep
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Waveform {
+    #[default]
+    Square,
+    Sine,
+    Triangle,
+    Sawtooth
+}
+
+impl Waveform {
+    /// Sample of the wave between -1.0 and 1.0 at the given phase between 0.0 and 1.0
+    pub fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (phase * 2.0 * std::f32::consts::PI).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * phase - 1.0
+        }
+    }
+}
+
+pub struct Oscillator {
     phase_inc: f32,
     phase: f32,
-    volume: f32
+    volume: f32,
+    waveform: Waveform,
+
+    /// Output sample rate
+    freq: f32,
+
+    /// XO-CHIP audio pattern played instead of the waveform if set
+    pattern: Option<[u8; 16]>,
+
+    /// Position in the pattern in bits
+    pattern_pos: f32,
+
+    /// Pattern bits played per output sample
+    pattern_inc: f32
 }
 
-impl AudioCallback for SquareWave {
+impl Oscillator {
+    /// An oscillator playing the waveform at `DEFAULT_FREQUENCY` and `DEFAULT_VOLUME` into
+    /// `sample_rate` samples per second
+    pub fn new(waveform: Waveform, sample_rate: i32) -> Oscillator {
+        Oscillator {
+            phase_inc: DEFAULT_FREQUENCY / sample_rate as f32,
+            phase: 0.0,
+            volume: DEFAULT_VOLUME,
+            waveform,
+            freq: sample_rate as f32,
+            pattern: None,
+            pattern_pos: 0.0,
+            pattern_inc: 0.0,
+        }
+    }
+
+    fn pattern_sample(&mut self, pattern: &[u8; 16]) -> f32 {
+        let bit = self.pattern_pos as usize % 128;
+        self.pattern_pos = (self.pattern_pos + self.pattern_inc) % 128.0;
+
+        if (pattern[bit / 8] >> (7 - bit % 8)) & 1 == 1 { 1.0 } else { -1.0 }
+    }
+}
+
+impl AudioCallback for Oscillator {
     type Channel = f32;
 
     fn callback(&mut self, out: &mut [Self::Channel]) {
         for x in out.iter_mut() {
-            *x = self.volume * if self.phase < 0.5 { 1.0 } else { -1.0 };
+            *x = self.volume * match self.pattern {
+                Some(pattern) => self.pattern_sample(&pattern),
+                None => self.waveform.sample(self.phase)
+            };
             self.phase = (self.phase + self.phase_inc) % 1.0;
         }
     }
 }
 
 pub struct Audio {
-    device: AudioDevice<SquareWave>
+    device: AudioDevice<Oscillator>
 }
 
 impl Audio {
@@ -40,15 +110,11 @@ impl Audio {
                 println!("{:?}", spec);
 
                 // initialize the audio callback
-                SquareWave {
-                    phase_inc: 240.0 / spec.freq as f32,
-                    phase: 0.0,
-                    volume: 0.25,
-                }
+                Oscillator::new(Waveform::default(), spec.freq)
             })
             .unwrap();
 
-        Audio { device: device }
+        Audio { device }
     }
 
     pub fn start_beep(&self) {
@@ -57,4 +123,40 @@ impl Audio {
     pub fn stop_beep(&self) {
         self.device.pause();
     }
-}
\ No newline at end of file
+
+    /// Sets the loudness of the beep, from 0.0 for silence to 1.0 for full volume
+    pub fn set_volume(&mut self, volume: f32) {
+        self.device.lock().volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// Sets the pitch of the beep in Hz, clamped to `FREQUENCY_RANGE`. The phase of the wave is
+    /// kept, so changing the pitch while beeping doesn't click
+    pub fn set_frequency(&mut self, hz: f32) {
+        let mut wave = self.device.lock();
+        let hz = hz.clamp(FREQUENCY_RANGE.0, FREQUENCY_RANGE.1);
+
+        wave.phase_inc = hz / wave.freq;
+    }
+
+    /// Sets the shape of the beep
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        self.device.lock().waveform = waveform;
+    }
+
+    /// Plays the XO-CHIP audio pattern at the given pitch while beeping instead of the
+    /// waveform. Passing `None` goes back to the waveform
+    pub fn set_pattern(&mut self, pattern: Option<[u8; 16]>, pitch: u8) {
+        let mut wave = self.device.lock();
+        let rate = 4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0);
+
+        wave.pattern_inc = rate / wave.freq;
+        wave.pattern = pattern;
+    }
+}
+
+impl Drop for Audio {
+    /// Stops the device so no tone lingers while the rest of SDL shuts down
+    fn drop(&mut self) {
+        self.stop_beep();
+    }
+}
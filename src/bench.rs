@@ -0,0 +1,63 @@
+//! Runs a ROM headless as fast as possible and reports the throughput, to track the speed of
+//! the vm over time. Every run executes the same opcodes: the random number generator is seeded
+//! and no keys are pressed
+use std::time::Instant;
+
+use chipvm::{cartridge, processor};
+
+/// Played unless another ROM is given. Brix moves sprites and checks collisions every frame,
+/// like most games
+const DEFAULT_ROM: &[u8] = include_bytes!("../games/BRIX");
+
+const DEFAULT_FRAMES: usize = 100_000;
+
+/// Opcodes per frame, well beyond what games need so drawing and timers don't dominate
+const CYCLES_PER_FRAME: usize = 1000;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let rom = match args.get(1) {
+        Some(path) => std::fs::read(path).unwrap_or_else(|e| {
+            eprintln!("Couldn't read ROM {}: {}", path, e);
+            std::process::exit(1);
+        }),
+        None => DEFAULT_ROM.to_vec()
+    };
+    let frames = match args.get(2).map(|frames| frames.parse()) {
+        Some(Ok(frames)) => frames,
+        Some(Err(_)) => {
+            eprintln!("Usage: {} [ROM] [FRAMES]", args[0]);
+            std::process::exit(2);
+        }
+        None => DEFAULT_FRAMES
+    };
+
+    let cartridge = match cartridge::Cartridge::from_bytes(&rom) {
+        Ok(cartridge) => cartridge,
+        Err(e) => {
+            eprintln!("Couldn't read ROM: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut processor = processor::Processor::with_seed(0);
+    processor.set_speed(CYCLES_PER_FRAME);
+    if let Err(e) = processor.load_program(&cartridge.rom) {
+        eprintln!("Couldn't load ROM: {}", e);
+        std::process::exit(1);
+    }
+
+    let started = Instant::now();
+    let mut opcodes = 0;
+    for _ in 0..frames {
+        let state = processor.tick([false; 16]);
+        if let Some(e) = state.error {
+            eprintln!("Emulation stopped at {:#05x}: {}", processor.pc(), e);
+            std::process::exit(1);
+        }
+        opcodes += state.cycles;
+    }
+    let seconds = started.elapsed().as_secs_f64();
+
+    println!("{} frames, {} opcodes in {:.3}s", frames, opcodes, seconds);
+    println!("{:.0} frames/s, {:.0} opcodes/s", frames as f64 / seconds, opcodes as f64 / seconds);
+}
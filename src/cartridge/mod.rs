@@ -1,18 +1,116 @@
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use crate::quirks::{Platform, Quirks};
+
+/// The first bytes of every gzip file
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// ROM path that stands for standard input
+pub const STDIN_PATH: &str = "-";
+
+/// A loaded ROM. Every way of loading one transparently decompresses gzipped ROMs
 pub struct Cartridge {
     pub rom: Vec<u8>,
+
+    /// How many bytes were read from the source, which is the compressed size for gzipped ROMs
     pub bytes_read: usize
 }
 
 impl Cartridge {
-    pub fn read(filename: &str) -> Cartridge {
-        let bytes = match std::fs::read(filename) {
-            Ok(b) => b,
-            Err(e) => panic!("{}", e)
-        };
+    pub fn read(filename: &str) -> Result<Cartridge, std::io::Error> {
+        let bytes = std::fs::read(filename)?;
 
-        Cartridge {
-            rom: bytes.clone(),
-            bytes_read: bytes.len()
+        Cartridge::from_bytes(&bytes)
+    }
+
+    /// Like `read`, but reads the ROM from standard input until EOF if `filename` is `STDIN_PATH`,
+    /// so ROMs can be piped in from other tools
+    pub fn open(filename: &str) -> Result<Cartridge, std::io::Error> {
+        if filename == STDIN_PATH {
+            Cartridge::from_reader(std::io::stdin())
+        }
+        else {
+            Cartridge::read(filename)
         }
     }
-}
\ No newline at end of file
+
+    /// Creates a cartridge from a ROM that's already in memory. Fails if the ROM looks
+    /// gzipped but doesn't decompress
+    pub fn from_bytes(bytes: &[u8]) -> Result<Cartridge, std::io::Error> {
+        let rom = if bytes.starts_with(&GZIP_MAGIC) {
+            let mut rom = Vec::new();
+            GzDecoder::new(bytes).read_to_end(&mut rom)?;
+            rom
+        }
+        else {
+            bytes.to_vec()
+        };
+
+        Ok(Cartridge {
+            bytes_read: bytes.len(),
+            rom
+        })
+    }
+
+    /// Reads the ROM from `reader` until it runs out
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Cartridge, std::io::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        Cartridge::from_bytes(&bytes)
+    }
+}
+
+/// What's known about a ROM found in the database of known games
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RomInfo {
+    /// Title of the game
+    pub name: &'static str,
+
+    /// The quirks the game needs to run correctly
+    pub quirks: Quirks
+}
+
+/// Quirks of the CHIP-48 interpreter the games bundled in `games/` were collected for
+const CHIP48: Quirks = Quirks::for_platform(Platform::Chip48);
+
+/// Known games keyed by the `rom_hash` of their ROM
+static KNOWN_ROMS: &[(u64, RomInfo)] = &[
+    (0xe59fd57fa44ecb40, RomInfo { name: "15 Puzzle", quirks: CHIP48 }),
+    (0x0fd332d0bc68c9f2, RomInfo { name: "Blinky", quirks: CHIP48 }),
+    (0x29bcab9b664d212b, RomInfo { name: "Blitz", quirks: CHIP48 }),
+    (0xc86e8ff63fce668c, RomInfo { name: "Brix", quirks: CHIP48 }),
+    (0xadf99268db3c3bc9, RomInfo { name: "Connect 4", quirks: CHIP48 }),
+    (0x1bbb10c8e5cadbb5, RomInfo { name: "Guess", quirks: CHIP48 }),
+    (0x3f58eb4fa83dcd98, RomInfo { name: "Hidden", quirks: CHIP48 }),
+    (0x8e547ebb12c026b4, RomInfo { name: "Space Invaders", quirks: CHIP48 }),
+    (0xa8e9391ebb18df6f, RomInfo { name: "Kaleidoscope", quirks: CHIP48 }),
+    (0x25e96e1086ce43cb, RomInfo { name: "Maze", quirks: CHIP48 }),
+    (0x43def5533f6d8d25, RomInfo { name: "Merlin", quirks: CHIP48 }),
+    (0x71cdb8b926f1b988, RomInfo { name: "Missile Command", quirks: CHIP48 }),
+    (0x624b3eed64313f42, RomInfo { name: "Pong", quirks: CHIP48 }),
+    (0x0f81c6a74dcd366e, RomInfo { name: "Pong 2", quirks: CHIP48 }),
+    (0x36f264b8f72349a6, RomInfo { name: "Puzzle", quirks: CHIP48 }),
+    (0xec7ca0de3e110327, RomInfo { name: "Syzygy", quirks: CHIP48 }),
+    (0x3e2c2d43b296b74c, RomInfo { name: "Tank", quirks: CHIP48 }),
+    (0x04eb2109dc29b1ab, RomInfo { name: "Tetris", quirks: CHIP48 }),
+    (0x56049e83866b207d, RomInfo { name: "Tic-Tac-Toe", quirks: CHIP48 }),
+    (0x8d8a02fa3a2ed293, RomInfo { name: "UFO", quirks: CHIP48 }),
+    (0xcdaa32787deaa913, RomInfo { name: "Vertical Brix", quirks: CHIP48 }),
+    (0xeae1357f230d90c5, RomInfo { name: "Vers", quirks: CHIP48 }),
+    (0xb7e1d74b387bede6, RomInfo { name: "Wipe Off", quirks: CHIP48 }),
+];
+
+/// Looks the ROM up in the database of known games
+pub fn detect(rom: &[u8]) -> Option<RomInfo> {
+    let hash = rom_hash(rom);
+
+    KNOWN_ROMS.iter().find(|(known, _)| *known == hash).map(|(_, info)| *info)
+}
+
+/// 64 bit FNV-1a hash of the ROM. Not cryptographic, but plenty to tell ROMs apart
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::quirks::Quirks;
+use crate::timing::DEFAULT_TARGET_IPS;
+
+/// Path the config is read from if none is given
+pub const DEFAULT_CONFIG_PATH: &str = "chipvm.toml";
+
+/// Settings read from a TOML file at startup. Anything left out of the file keeps its default:
+///
+/// ```toml
+/// scale = 12
+/// target_ips = 700
+/// volume = 0.5
+/// fg_color = [255, 255, 255]
+/// bg_color = [0, 0, 64]
+///
+/// [quirks]
+/// shift_uses_vy = true
+///
+/// [keys]
+/// Up = 2
+/// Left = 4
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Quirks to run with. Replaces those picked for known games if the file has a `[quirks]` table
+    pub quirks: Option<Quirks>,
+
+    /// Key names, as SDL names them, bound to chip-8 keys. Replaces the default bindings if
+    /// not empty
+    pub keys: HashMap<String, u8>,
+
+    /// Color of set pixels as red, green and blue
+    pub fg_color: [u8; 3],
+
+    /// Color of unset pixels as red, green and blue
+    pub bg_color: [u8; 3],
+
+    /// Color of the grid lines between pixels as red, green and blue
+    pub grid_color: [u8; 3],
+
+    /// Size of a low resolution chip-8 pixel on screen
+    pub scale: u32,
+
+    /// Opcodes executed per second
+    pub target_ips: u32,
+
+    /// Loudness of the beep, from 0.0 to 1.0
+    pub volume: f32,
+
+    /// Unset pixels fade out over a few frames instead of disappearing at once, to hide flicker
+    pub persistence: bool,
+
+    /// Keys pressed again within this many milliseconds of being released are ignored, for
+    /// keyboards and controllers that bounce. 0 turns it off
+    pub debounce_ms: u64,
+
+    /// Frames pc may stay at the same address before a possible infinite loop is reported.
+    /// 0 turns it off
+    pub watchdog_frames: usize,
+
+    /// The emulation pauses while the window is in the background
+    pub pause_on_focus_loss: bool
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            quirks: None,
+            keys: HashMap::new(),
+            fg_color: [0, 250, 0],
+            bg_color: [0, 0, 0],
+            grid_color: [40, 40, 40],
+            scale: 10,
+            target_ips: DEFAULT_TARGET_IPS,
+            volume: 0.25,
+            persistence: false,
+            debounce_ms: 0,
+            watchdog_frames: 0,
+            pause_on_focus_loss: true
+        }
+    }
+}
+
+/// Errors that can happen while loading a config
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(toml::de::Error)
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "{}", e),
+            ConfigError::Parse(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        toml::from_str(text).map_err(ConfigError::Parse)
+    }
+
+    pub fn load(path: &str) -> Result<Config, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+        Config::parse(&text)
+    }
+
+    /// Loads the config at `path`, or the defaults if there's no file there
+    pub fn load_or_default(path: &str) -> Result<Config, ConfigError> {
+        match Config::load(path) {
+            Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            result => result
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::processor::PROGRAM_START;
+
+/// Turns an opcode into human readable assembly like `LD V3, 0x2A` or `DRW V0, V1, 5`.
+/// Opcodes the processor doesn't decode are rendered as `DW 0xNNNN`
+pub fn disassemble_opcode(opcode: u16) -> String {
+    let nibbles = (
+        (opcode & 0xF000) >> 12,
+        (opcode & 0x0F00) >> 8,
+        (opcode & 0x00F0) >> 4,
+        opcode & 0x000F,
+    );
+
+    let nnn = opcode & 0x0FFF;
+    let kk = opcode & 0x00FF;
+    let x = nibbles.1;
+    let y = nibbles.2;
+    let n = nibbles.3;
+
+    match nibbles {
+        (0x00, 0x00, 0x0c, _) => format!("SCD {}", n),
+        (0x00, 0x00, 0x0e, 0x00) => "CLS".to_string(),
+        (0x00, 0x00, 0x0e, 0x0e) => "RET".to_string(),
+        (0x00, 0x00, 0x0f, 0x0b) => "SCR".to_string(),
+        (0x00, 0x00, 0x0f, 0x0c) => "SCL".to_string(),
+        (0x00, 0x00, 0x0f, 0x0d) => "EXIT".to_string(),
+        (0x00, 0x00, 0x0f, 0x0e) => "LOW".to_string(),
+        (0x00, 0x00, 0x0f, 0x0f) => "HIGH".to_string(),
+        (0x01, _, _, _) => format!("JP {:#05X}", nnn),
+        (0x02, _, _, _) => format!("CALL {:#05X}", nnn),
+        (0x03, _, _, _) => format!("SE V{:X}, {:#04X}", x, kk),
+        (0x04, _, _, _) => format!("SNE V{:X}, {:#04X}", x, kk),
+        (0x05, _, _, 0x00) => format!("SE V{:X}, V{:X}", x, y),
+        (0x06, _, _, _) => format!("LD V{:X}, {:#04X}", x, kk),
+        (0x07, _, _, _) => format!("ADD V{:X}, {:#04X}", x, kk),
+        (0x08, _, _, 0x00) => format!("LD V{:X}, V{:X}", x, y),
+        (0x08, _, _, 0x01) => format!("OR V{:X}, V{:X}", x, y),
+        (0x08, _, _, 0x02) => format!("AND V{:X}, V{:X}", x, y),
+        (0x08, _, _, 0x03) => format!("XOR V{:X}, V{:X}", x, y),
+        (0x08, _, _, 0x04) => format!("ADD V{:X}, V{:X}", x, y),
+        (0x08, _, _, 0x05) => format!("SUB V{:X}, V{:X}", x, y),
+        (0x08, _, _, 0x06) => format!("SHR V{:X}, V{:X}", x, y),
+        (0x08, _, _, 0x07) => format!("SUBN V{:X}, V{:X}", x, y),
+        (0x08, _, _, 0x0e) => format!("SHL V{:X}, V{:X}", x, y),
+        (0x09, _, _, 0x00) => format!("SNE V{:X}, V{:X}", x, y),
+        (0x0a, _, _, _) => format!("LD I, {:#05X}", nnn),
+        (0x0b, _, _, _) => format!("JP V0, {:#05X}", nnn),
+        (0x0c, _, _, _) => format!("RND V{:X}, {:#04X}", x, kk),
+        (0x0d, _, _, _) => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        (0x0e, _, 0x09, 0x0e) => format!("SKP V{:X}", x),
+        (0x0e, _, 0x0a, 0x01) => format!("SKNP V{:X}", x),
+        (0x0f, 0x00, 0x00, 0x02) => "AUDIO".to_string(),
+        (0x0f, _, 0x00, 0x01) => format!("PLANE {}", x),
+        (0x0f, _, 0x00, 0x07) => format!("LD V{:X}, DT", x),
+        (0x0f, _, 0x00, 0x0a) => format!("LD V{:X}, K", x),
+        (0x0f, _, 0x01, 0x05) => format!("LD DT, V{:X}", x),
+        (0x0f, _, 0x01, 0x08) => format!("LD ST, V{:X}", x),
+        (0x0f, _, 0x01, 0x0e) => format!("ADD I, V{:X}", x),
+        (0x0f, _, 0x02, 0x09) => format!("LD F, V{:X}", x),
+        (0x0f, _, 0x03, 0x00) => format!("LD HF, V{:X}", x),
+        (0x0f, _, 0x03, 0x03) => format!("LD B, V{:X}", x),
+        (0x0f, _, 0x03, 0x0a) => format!("PITCH V{:X}", x),
+        (0x0f, _, 0x05, 0x05) => format!("LD [I], V{:X}", x),
+        (0x0f, _, 0x06, 0x05) => format!("LD V{:X}, [I]", x),
+        (0x0f, _, 0x07, 0x05) => format!("LD R, V{:X}", x),
+        (0x0f, _, 0x08, 0x05) => format!("LD V{:X}, R", x),
+        _ => format!("DW {:#06X}", opcode)
+    }
+}
+
+/// Disassembles a whole ROM, pairing each instruction with the address it's loaded at.
+/// Instructions are two bytes, except the XO-CHIP long load `F000 NNNN` which is rendered as
+/// `LD I, long 0xNNNN`. A trailing odd byte is rendered as `DB 0xNN`
+pub fn disassemble_rom(bytes: &[u8]) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let word = |at: usize| (bytes[at] as u16) << 8 | (bytes[at + 1] as u16);
+        let (text, len) = match bytes.len() - offset {
+            1 => (format!("DB {:#04X}", bytes[offset]), 1),
+            left if left >= 4 && word(offset) == 0xF000 => (format!("LD I, long {:#06X}", word(offset + 2)), 4),
+            _ => (disassemble_opcode(word(offset)), 2)
+        };
+
+        lines.push((PROGRAM_START + offset, text));
+        offset += len;
+    }
+
+    lines
+}
@@ -1,64 +1,490 @@
-/// https://github.com/starrhorne/chip8-rust/blob/master/src/drivers/display_driver.rs
+// https://github.com/starrhorne/chip8-rust/blob/master/src/drivers/display_driver.rs
 
 use sdl2;
 use sdl2::pixels;
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+
+use std::time::{Duration, Instant};
+
+use crate::menu::{glyph, CELL_WIDTH, LINE_HEIGHT};
+use crate::output::{Vram, VRAM_WIDTH, VRAM_HEIGHT};
+
+use crate::timing::Rates;
+
+/// Title of the window, followed by the measured rates if those are shown
+const WINDOW_TITLE: &str = "chipvm";
 
 const CHIP8_HEIGHT: usize = 32;
 const CHIP8_WIDTH: usize = 64;
 
-const SCALE_FACTOR: u32 = 20;
-const SCREEN_WIDTH: u32 = (CHIP8_WIDTH as u32) * SCALE_FACTOR;
-const SCREEN_HEIGHT: u32 = (CHIP8_HEIGHT as u32) * SCALE_FACTOR;
+/// Size of a low resolution chip-8 pixel on screen unless told otherwise
+pub const DEFAULT_SCALE: u32 = 10;
+
+/// Color of set pixels unless told otherwise
+pub const DEFAULT_FG_COLOR: pixels::Color = pixels::Color { r: 0, g: 250, b: 0, a: 0xff };
+
+/// Color of unset pixels unless told otherwise
+pub const DEFAULT_BG_COLOR: pixels::Color = pixels::Color { r: 0, g: 0, b: 0, a: 0xff };
+
+/// Color of pixels only set in the second XO-CHIP plane unless told otherwise
+pub const DEFAULT_PLANE2_COLOR: pixels::Color = pixels::Color { r: 250, g: 170, b: 0, a: 0xff };
+
+/// Frames a pixel takes to fade out after it's unset when display persistence is on
+pub const PERSISTENCE_FRAMES: u8 = 3;
+
+/// Color of the grid lines between pixels unless told otherwise
+pub const DEFAULT_GRID_COLOR: pixels::Color = pixels::Color { r: 40, g: 40, b: 40, a: 0xff };
 
-pub struct DisplayDriver {
+/// Smallest on-screen size of a chip-8 pixel the grid is drawn at. Below that the lines
+/// would cover most of the pixels
+pub const GRID_MIN_PIXEL_SIZE: u32 = 4;
+
+/// Color of pixels set in both XO-CHIP planes unless told otherwise
+pub const DEFAULT_OVERLAP_COLOR: pixels::Color = pixels::Color { r: 250, g: 250, b: 250, a: 0xff };
+
+/// How long a toast stays on screen
+pub const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+/// Characters that fit on a line of a toast. Toasts are drawn with the boot menu glyphs at the
+/// high resolution pixel size, a pixel away from the left edge
+pub const TOAST_COLUMNS: usize = (VRAM_WIDTH - 1) / CELL_WIDTH;
+
+/// Lines of a toast that fit on the screen
+pub const TOAST_LINES: usize = VRAM_HEIGHT / LINE_HEIGHT;
+
+/// Draws the vram into a window opened with `open_window`. Borrows the texture creator of the
+/// window's canvas, which the frontend owns, since the frame texture borrows its creator
+pub struct DisplayDriver<'t> {
     canvas: Canvas<Window>,
+
+    /// Creates the frame texture, again whenever the scale changes
+    texture_creator: &'t TextureCreator<WindowContext>,
+
+    /// Holds the drawn frame between draws, so only changed pixels have to be repainted.
+    /// The window's own back buffer can't be used for that, its contents are undefined after a present
+    frame: Texture<'t>,
+
+    /// The shades and resolution drawn last, or `None` if the next draw has to repaint everything
+    last_frame: Option<(Vram, bool)>,
+
+    /// Shade of every pixel as of the last draw. The low two bits are the palette entry and
+    /// the rest how many frames the pixel has left to fade out, 0 for pixels that aren't fading
+    shades: Vram,
+
+    /// Unset pixels fade out over `PERSISTENCE_FRAMES` frames instead of disappearing at once
+    persistence: bool,
+
+    /// Set pixels are drawn in the background color and unset ones in the foreground color
+    invert: bool,
+
+    /// Lines are drawn between the pixels, if they're large enough
+    grid: bool,
+
+    /// The lines of the toast shown in the bottom left corner and when it was shown
+    toast: Option<(Vec<String>, Instant)>,
+
+    scale: u32,
+    fg_color: pixels::Color,
+    bg_color: pixels::Color,
+    plane2_color: pixels::Color,
+    overlap_color: pixels::Color,
+    grid_color: pixels::Color,
 }
 
-impl DisplayDriver {
-    pub fn new(sdl_context: &sdl2::Sdl) -> Self {
-        let video_subsys = sdl_context.video().unwrap();
-        let window = video_subsys
-            .window(
-                "rust-sdl2_gfx: draw line & FPSManager",
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-            )
-            .position_centered()
-            .opengl()
-            .build()
-            .unwrap();
-
-        let mut canvas = window.into_canvas().build().unwrap();
-
-        canvas.set_draw_color(pixels::Color::RGB(0, 0, 0));
-        canvas.clear();
-        canvas.present();
-
-        DisplayDriver { canvas: canvas }
-    }
-
-    pub fn draw(&mut self, pixels: &[[u8; CHIP8_WIDTH]; CHIP8_HEIGHT]) {
-        for (y, row) in pixels.iter().enumerate() {
-            for (x, &col) in row.iter().enumerate() {
-                let x = (x as u32) * SCALE_FACTOR;
-                let y = (y as u32) * SCALE_FACTOR;
-
-                self.canvas.set_draw_color(color(col));
-                let _ = self.canvas
-                    .fill_rect(Rect::new(x as i32, y as i32, SCALE_FACTOR, SCALE_FACTOR));
+/// Opens a window big enough to show the 64*32 display with every pixel `scale` pixels wide.
+/// Hand it to `DisplayDriver::new` together with its `texture_creator`
+pub fn open_window(sdl_context: &sdl2::Sdl, scale: u32) -> Canvas<Window> {
+    let (width, height) = window_size(scale);
+    let video_subsys = sdl_context.video().unwrap();
+    let window = video_subsys
+        .window(
+            WINDOW_TITLE,
+            width,
+            height,
+        )
+        .position_centered()
+        .opengl()
+        .build()
+        .unwrap();
+
+    let mut canvas = window.into_canvas().build().unwrap();
+
+    canvas.set_draw_color(DEFAULT_BG_COLOR);
+    canvas.clear();
+    canvas.present();
+
+    canvas
+}
+
+impl<'t> DisplayDriver<'t> {
+    /// Draws into the window of `canvas`, opened with `open_window` at the given scale.
+    /// `texture_creator` has to be the canvas's own
+    pub fn new(canvas: Canvas<Window>, texture_creator: &'t TextureCreator<WindowContext>, scale: u32) -> Self {
+        let frame = frame_texture(texture_creator, scale);
+
+        DisplayDriver {
+            canvas,
+            texture_creator,
+            frame,
+            last_frame: None,
+            shades: [[0; VRAM_WIDTH]; VRAM_HEIGHT],
+            persistence: false,
+            invert: false,
+            grid: false,
+            toast: None,
+            scale,
+            fg_color: DEFAULT_FG_COLOR,
+            bg_color: DEFAULT_BG_COLOR,
+            plane2_color: DEFAULT_PLANE2_COLOR,
+            overlap_color: DEFAULT_OVERLAP_COLOR,
+            grid_color: DEFAULT_GRID_COLOR,
+        }
+    }
+
+    /// Sets the colors of set and unset pixels. Takes effect on the next `draw`
+    pub fn set_colors(&mut self, fg: pixels::Color, bg: pixels::Color) {
+        self.fg_color = fg;
+        self.bg_color = bg;
+        self.last_frame = None;
+    }
+
+    /// Sets the colors of pixels set only in the second XO-CHIP plane and pixels set in both
+    /// planes. Pixels set only in the first plane use the foreground color
+    pub fn set_plane_colors(&mut self, plane2: pixels::Color, overlap: pixels::Color) {
+        self.plane2_color = plane2;
+        self.overlap_color = overlap;
+        self.last_frame = None;
+    }
+
+    /// Resizes the window for a new scale. Takes effect on the next `draw`
+    pub fn set_scale(&mut self, scale: u32) {
+        let (width, height) = window_size(scale);
+        self.canvas.window_mut().set_size(width, height).unwrap();
+        self.frame = frame_texture(self.texture_creator, scale);
+        self.last_frame = None;
+        self.scale = scale;
+    }
+
+    /// Turns display persistence on or off. With it on, unset pixels fade out over a few frames
+    /// instead of disappearing at once, which hides the flicker of games that erase and redraw
+    /// their sprites every frame. Off by default
+    pub fn set_persistence(&mut self, persistence: bool) {
+        self.persistence = persistence;
+    }
+
+    /// Turns negative video on or off. It swaps the foreground and background colors of
+    /// whatever palette is active, without touching the game. Takes effect on the next `draw`
+    pub fn set_invert(&mut self, invert: bool) {
+        self.invert = invert;
+        self.last_frame = None;
+    }
+
+    pub fn invert(&self) -> bool {
+        self.invert
+    }
+
+    /// Turns the grid lines between pixels on or off, to check where sprites land. They're
+    /// only drawn while a pixel is at least `GRID_MIN_PIXEL_SIZE` wide. Takes effect on the
+    /// next `draw`
+    pub fn set_grid(&mut self, grid: bool) {
+        self.grid = grid;
+    }
+
+    pub fn grid(&self) -> bool {
+        self.grid
+    }
+
+    pub fn set_grid_color(&mut self, color: pixels::Color) {
+        self.grid_color = color;
+    }
+
+    /// Shows the measured frames and opcodes per second in the window title, or hides them
+    /// if `rates` is `None`
+    pub fn show_rates(&mut self, rates: Option<Rates>) {
+        let title = match rates {
+            Some(rates) => format!("{} - {} FPS, {} IPS", WINDOW_TITLE, rates.fps, rates.ips),
+            None => WINDOW_TITLE.to_string()
+        };
+
+        let _ = self.canvas.window_mut().set_title(&title);
+    }
+
+    /// Shows a short message over the bottom left corner of the screen for `TOAST_DURATION`,
+    /// e.g. to confirm a hotkey. It replaces the toast shown before. Takes effect on the next `draw`
+    pub fn show_toast(&mut self, message: &str) {
+        self.toast = Some((toast_lines(message), Instant::now()));
+    }
+
+    /// Set while a toast is shown, or until the `draw` that removes it once it expired. `draw`
+    /// has to be called every frame until then, even if the vram didn't change
+    pub fn has_toast(&self) -> bool {
+        self.toast.is_some()
+    }
+
+    /// Set while pixels are still fading out, in which case `draw` has to be called every frame
+    /// even if the vram didn't change
+    pub fn is_fading(&self) -> bool {
+        self.shades.iter().flatten().any(|&shade| shade >> 2 != 0)
+    }
+
+    /// Draws the vram. Only the top left 64*32 pixels are drawn unless `hires` is set, in
+    /// which case each pixel is drawn at half the size to fit the window.
+    ///
+    /// Only the pixels that changed since the last draw are repainted. A typical frame moves a
+    /// sprite or two, so that's a few dozen rectangles instead of all 2048, or 8192 in high
+    /// resolution mode. Everything is repainted after a resolution, scale or color change
+    pub fn draw(&mut self, pixels: &Vram, hires: bool) {
+        let (width, height) = if hires {
+            (VRAM_WIDTH, VRAM_HEIGHT)
+        }
+        else {
+            (CHIP8_WIDTH, CHIP8_HEIGHT)
+        };
+
+        let palette = self.palette();
+        let scale = self.scale;
+        let last = match self.last_frame {
+            Some((last, last_hires)) if last_hires == hires => Some(last),
+            _ => None
+        };
+        if last.is_none() {
+            self.shades = [[0; VRAM_WIDTH]; VRAM_HEIGHT];
+        }
+
+        for (shade_row, row) in self.shades.iter_mut().zip(pixels.iter()).take(height) {
+            for (shade, &col) in shade_row.iter_mut().zip(row.iter()).take(width) {
+                *shade = next_shade(*shade, col & 0b11, self.persistence);
+            }
+        }
+        let shades = self.shades;
+
+        let _ = self.canvas.with_texture_canvas(&mut self.frame, |canvas| {
+            for (y, row) in shades.iter().take(height).enumerate() {
+                for (x, &shade) in row.iter().take(width).enumerate() {
+                    if last.is_some_and(|last| last[y][x] == shade) {
+                        continue;
+                    }
+
+                    canvas.set_draw_color(shade_color(shade, &palette));
+                    let _ = canvas.fill_rect(pixel_rect(x, y, width, height, scale));
+                }
             }
+        });
+        let _ = self.canvas.copy(&self.frame, None, None);
+        // The grid goes on top of the copy, so the frame texture only ever holds pixels
+        if self.grid {
+            self.canvas.set_draw_color(self.grid_color);
+            let _ = self.canvas.fill_rects(&grid_lines(width, height, scale));
+        }
+        // So does the toast, which then disappears with the next copy once it expired
+        if self.toast.as_ref().is_some_and(|(_, shown)| shown.elapsed() >= TOAST_DURATION) {
+            self.toast = None;
+        }
+        if let Some((lines, _)) = &self.toast {
+            self.canvas.set_draw_color(palette[0]);
+            let _ = self.canvas.fill_rect(toast_area(lines, scale));
+            self.canvas.set_draw_color(palette[1]);
+            let rects: Vec<Rect> = toast_pixels(lines)
+                .into_iter()
+                .map(|(x, y)| pixel_rect(x, y, VRAM_WIDTH, VRAM_HEIGHT, scale))
+                .collect();
+            let _ = self.canvas.fill_rects(&rects);
         }
         self.canvas.present();
+
+        self.last_frame = Some((shades, hires));
+    }
+
+    /// Saves the vram as a PNG image, using the same scale and colors as the window
+    pub fn screenshot(&self, pixels: &Vram, hires: bool, path: &str) -> image::ImageResult<()> {
+        render(pixels, hires, self.scale, self.palette()).save(path)
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Colors of the pixel values 0 to 3, i.e. unset, first plane, second plane and both planes.
+    /// Inverted if negative video is on
+    pub fn palette(&self) -> [pixels::Color; 4] {
+        let palette = [self.bg_color, self.fg_color, self.plane2_color, self.overlap_color];
+
+        if self.invert {
+            inverted(palette)
+        }
+        else {
+            palette
+        }
+    }
+}
+
+/// The palette with the colors of unset pixels and pixels set in the first plane swapped
+pub fn inverted(palette: [pixels::Color; 4]) -> [pixels::Color; 4] {
+    [palette[1], palette[0], palette[2], palette[3]]
+}
+
+/// Renders the vram into an image the size of the window at the given scale, coloring pixel
+/// values 0 to 3 with the matching palette entry
+pub fn render(pixels: &Vram, hires: bool, scale: u32, palette: [pixels::Color; 4]) -> image::RgbImage {
+    let (width, height) = if hires {
+        (VRAM_WIDTH, VRAM_HEIGHT)
     }
+    else {
+        (CHIP8_WIDTH, CHIP8_HEIGHT)
+    };
+    let (image_width, image_height) = window_size(scale);
+    let mut image = image::RgbImage::new(image_width, image_height);
+
+    for (y, row) in pixels.iter().take(height).enumerate() {
+        for (x, &col) in row.iter().take(width).enumerate() {
+            let color = palette[col as usize & 0b11];
+            let rect = pixel_rect(x, y, width, height, scale);
+
+            for image_y in rect.top()..rect.bottom() {
+                for image_x in rect.left()..rect.right() {
+                    image.put_pixel(image_x as u32, image_y as u32, image::Rgb([color.r, color.g, color.b]));
+                }
+            }
+        }
+    }
+
+    image
 }
 
-fn color(value: u8) -> pixels::Color {
-    if value == 0 {
-        pixels::Color::RGB(0, 0, 0)
-    } else {
-        pixels::Color::RGB(0, 250, 0)
+/// The shade of a pixel after a frame, given its shade before the frame and its palette entry.
+/// Set pixels get their palette entry and unset ones that were set start fading out
+fn next_shade(shade: u8, col: u8, persistence: bool) -> u8 {
+    let (frames_left, faded_col) = (shade >> 2, shade & 0b11);
+
+    if col != 0 || !persistence {
+        col
+    }
+    else if frames_left == 0 && faded_col != 0 {
+        PERSISTENCE_FRAMES << 2 | faded_col
+    }
+    else if frames_left > 1 {
+        (frames_left - 1) << 2 | faded_col
     }
-}
\ No newline at end of file
+    else {
+        0
+    }
+}
+
+/// The color of a shade: the palette entry itself for pixels that aren't fading, otherwise
+/// a mix of it and the background that gets darker as the pixel fades
+fn shade_color(shade: u8, palette: &[pixels::Color; 4]) -> pixels::Color {
+    let (frames_left, col) = ((shade >> 2) as i32, palette[(shade & 0b11) as usize]);
+    if frames_left == 0 {
+        return col;
+    }
+
+    let bg = palette[0];
+    let mix = |from: u8, to: u8| (from as i32 + (to as i32 - from as i32) * frames_left / (PERSISTENCE_FRAMES as i32 + 1)) as u8;
+
+    pixels::Color::RGB(mix(bg.r, col.r), mix(bg.g, col.g), mix(bg.b, col.b))
+}
+
+/// A texture the size of the window at the given scale to draw frames into
+fn frame_texture(texture_creator: &TextureCreator<WindowContext>, scale: u32) -> Texture<'_> {
+    let (width, height) = window_size(scale);
+
+    texture_creator.create_texture_target(None, width, height).unwrap()
+}
+
+/// Size of the window at the given scale
+pub(crate) fn window_size(scale: u32) -> (u32, u32) {
+    ((CHIP8_WIDTH as u32) * scale, (CHIP8_HEIGHT as u32) * scale)
+}
+
+/// One pixel wide lines between the pixels of a `width`*`height` display at the given scale,
+/// along the same edges `pixel_rect` uses. Empty if the pixels are smaller than
+/// `GRID_MIN_PIXEL_SIZE`
+pub fn grid_lines(width: usize, height: usize, scale: u32) -> Vec<Rect> {
+    let (window_width, window_height) = window_size(scale);
+    if window_width / (width as u32) < GRID_MIN_PIXEL_SIZE {
+        return Vec::new();
+    }
+
+    let vertical = (1..width).map(|x| Rect::new(pixel_rect(x, 0, width, height, scale).left(), 0, 1, window_height));
+    let horizontal = (1..height).map(|y| Rect::new(0, pixel_rect(0, y, width, height, scale).top(), window_width, 1));
+
+    vertical.chain(horizontal).collect()
+}
+
+/// Breaks a message into lines of at most `TOAST_COLUMNS` characters, between words where
+/// possible. Words too long for a line are split and lines past `TOAST_LINES` are dropped
+pub fn toast_lines(message: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in message.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+
+        if !line.is_empty() && line.chars().count() + 1 + word.len() > TOAST_COLUMNS {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        while word.len() > TOAST_COLUMNS {
+            let rest = word.split_off(TOAST_COLUMNS);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines.truncate(TOAST_LINES);
+    lines
+}
+
+/// The set pixels of a toast made of `lines` on the high resolution grid. The last line sits
+/// at the bottom of the screen and each line starts a pixel from the left edge
+pub fn toast_pixels(lines: &[String]) -> Vec<(usize, usize)> {
+    let mut pixels = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let top = VRAM_HEIGHT - (lines.len() - index) * LINE_HEIGHT;
+        for (column, c) in line.chars().enumerate() {
+            let left = 1 + column * CELL_WIDTH;
+            for (row, &byte) in glyph(c).iter().enumerate() {
+                pixels.extend((0..4).filter(|bit| byte & (0x80 >> bit) != 0).map(|bit| (left + bit, top + row)));
+            }
+        }
+    }
+
+    pixels
+}
+
+/// The area of the window cleared behind a toast made of `lines`, which leaves a pixel of
+/// margin around the text
+fn toast_area(lines: &[String], scale: u32) -> Rect {
+    let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let top_left = pixel_rect(0, VRAM_HEIGHT - lines.len() * LINE_HEIGHT - 1, VRAM_WIDTH, VRAM_HEIGHT, scale);
+    let bottom_right = pixel_rect(columns * CELL_WIDTH, VRAM_HEIGHT - 1, VRAM_WIDTH, VRAM_HEIGHT, scale);
+
+    Rect::new(
+        top_left.left(),
+        top_left.top(),
+        (bottom_right.right() - top_left.left()) as u32,
+        (bottom_right.bottom() - top_left.top()) as u32
+    )
+}
+
+/// The area of the window covered by the pixel at x, y of a `width`*`height` display.
+/// Pixel edges are rounded down so the pixels tile the window even when they don't divide it evenly
+fn pixel_rect(x: usize, y: usize, width: usize, height: usize, scale: u32) -> Rect {
+    let (window_width, window_height) = window_size(scale);
+    let left = (x as u32) * window_width / (width as u32);
+    let right = (x as u32 + 1) * window_width / (width as u32);
+    let top = (y as u32) * window_height / (height as u32);
+    let bottom = (y as u32 + 1) * window_height / (height as u32);
+
+    Rect::new(left as i32, top as i32, right - left, bottom - top)
+}
+
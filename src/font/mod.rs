@@ -80,4 +80,58 @@ pub const FONT_SET: [u8; 80] = [
     0xF0,
     0x80,
     0x80,
-];
\ No newline at end of file
+];
+
+/// Height of a glyph in `FONT_SET` in bytes
+pub const FONT_HEIGHT: usize = 5;
+
+/// Address of the glyph for the hex digit in memory, where FX29 points I. Only the low nibble
+/// of `digit` is used
+pub fn font_address(digit: u8) -> usize {
+    (digit & 0xF) as usize * FONT_HEIGHT
+}
+
+/// The bytes of the glyph for the hex digit. Only the low nibble of `digit` is used
+pub fn font_sprite(digit: u8) -> &'static [u8] {
+    let start = font_address(digit);
+
+    &FONT_SET[start..start + FONT_HEIGHT]
+}
+
+/// The SUPER-CHIP large font, 8*10 pixel glyphs for the hex digits 0 to F
+pub const BIG_FONT_SET: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+/// Address the large font is loaded at, right after the small font
+pub const BIG_FONT_START: usize = FONT_SET.len();
+
+/// Height of a glyph in `BIG_FONT_SET` in bytes
+pub const BIG_FONT_HEIGHT: usize = 10;
+
+/// Address of the large glyph for the hex digit in memory, where FX30 points I. Only the low
+/// nibble of `digit` is used
+pub fn big_font_address(digit: u8) -> usize {
+    BIG_FONT_START + (digit & 0xF) as usize * BIG_FONT_HEIGHT
+}
+
+/// Copies both fonts to the start of memory
+pub fn load_fonts(memory: &mut [u8]) {
+    memory[..FONT_SET.len()].copy_from_slice(&FONT_SET);
+    memory[BIG_FONT_START..BIG_FONT_START + BIG_FONT_SET.len()].copy_from_slice(&BIG_FONT_SET);
+}
@@ -0,0 +1,13 @@
+use crate::output::Vram;
+use crate::processor::{LoadError, Processor};
+
+/// Runs a ROM for the given number of frames without any SDL drivers and with no keys pressed.
+/// Returns the vram after each frame, so the screen contents can be checked against known images
+pub fn run_headless(rom: &[u8], frames: usize) -> Result<Vec<Vram>, LoadError> {
+    let mut processor = Processor::new();
+    processor.load_program(rom)?;
+
+    Ok((0..frames)
+        .map(|_| *processor.tick([false; 16]).vram)
+        .collect())
+}
@@ -1,25 +1,313 @@
 use sdl2;
-use sdl2::event::Event;
+use sdl2::controller::{Button, GameController};
+use sdl2::GameControllerSubsystem;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
 
-/// https://github.com/starrhorne/chip8-rust/blob/master/src/drivers/input_driver.rs
+use crate::timing::{Clock, SystemClock};
+
+/// Maps keyboard keys, or any other kind of key, to chip-8 keys (0x0 to 0xF)
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMap<K: Eq + Hash = Keycode> {
+    keys: HashMap<K, u8>
+}
+
+/// Maps game controller buttons to chip-8 keys
+pub type ButtonMap = KeyMap<Button>;
+
+impl<K: Eq + Hash + Copy> KeyMap<K> {
+    /// Creates a keymap from key and chip-8 key pairs. Pairs with a chip-8 key above 0xF are ignored
+    pub fn from_pairs(pairs: &[(K, u8)]) -> KeyMap<K> {
+        KeyMap {
+            keys: pairs.iter().cloned().filter(|&(_, key)| key <= 0xf).collect()
+        }
+    }
+
+    /// The chip-8 key the key is bound to, if any
+    pub fn get(&self, key: K) -> Option<u8> {
+        self.keys.get(&key).cloned()
+    }
+
+    /// Every key and the chip-8 key it's bound to
+    pub fn pairs(&self) -> impl Iterator<Item = (K, u8)> + '_ {
+        self.keys.iter().map(|(&key, &chip8_key)| (key, chip8_key))
+    }
+
+    /// The chip-8 keypad with every key bound to one of the held `keys` down. Any number of
+    /// keys can be held at once
+    pub fn keypad<I: IntoIterator<Item = K>>(&self, keys: I) -> [bool; 16] {
+        let mut keypad = [false; 16];
+
+        for key in keys {
+            if let Some(i) = self.get(key) {
+                keypad[i as usize] = true;
+            }
+        }
+
+        keypad
+    }
+}
+
+impl KeyMap<Keycode> {
+    /// Creates a keymap from key names, as SDL names them, bound to chip-8 keys. Fails with the
+    /// first name SDL doesn't know
+    pub fn from_names(names: &HashMap<String, u8>) -> Result<KeyMap, String> {
+        let pairs = names
+            .iter()
+            .map(|(name, &key)| Keycode::from_name(name).map(|keycode| (keycode, key)).ok_or_else(|| name.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(KeyMap::from_pairs(&pairs))
+    }
+}
+
+impl Default for KeyMap<Button> {
+    /// Maps the D-pad onto 2/4/6/8, which most games use for movement, and the face buttons
+    /// onto 5 (A), 0 (B), A (X) and B (Y). Start is F and Back is E
+    fn default() -> ButtonMap {
+        KeyMap::from_pairs(&[
+            (Button::DPadUp, 0x2),
+            (Button::DPadLeft, 0x4),
+            (Button::DPadRight, 0x6),
+            (Button::DPadDown, 0x8),
+            (Button::A, 0x5),
+            (Button::B, 0x0),
+            (Button::X, 0xa),
+            (Button::Y, 0xb),
+            (Button::Start, 0xf),
+            (Button::Back, 0xe),
+        ])
+    }
+}
+
+impl Default for KeyMap<Keycode> {
+    /// The classic layout, mapping the chip-8 keypad onto the 1234/QWER/ASDF/ZXCV grid
+    fn default() -> KeyMap {
+        KeyMap::from_pairs(&[
+            (Keycode::Num1, 0x1),
+            (Keycode::Num2, 0x2),
+            (Keycode::Num3, 0x3),
+            (Keycode::Num4, 0xc),
+            (Keycode::Q, 0x4),
+            (Keycode::W, 0x5),
+            (Keycode::E, 0x6),
+            (Keycode::R, 0xd),
+            (Keycode::A, 0x7),
+            (Keycode::S, 0x8),
+            (Keycode::D, 0x9),
+            (Keycode::F, 0xe),
+            (Keycode::Z, 0xa),
+            (Keycode::X, 0x0),
+            (Keycode::C, 0xb),
+            (Keycode::V, 0xf),
+        ])
+    }
+}
+
+/// Ignores chip-8 keys pressed again too soon after they were released. Worn keys and some
+/// controllers bounce, which menu ROMs polling the keypad every frame see as several presses
+pub struct Debouncer<C: Clock> {
+    clock: C,
+    window: Duration,
+
+    /// The keys as they were last given to `filter`
+    held: [bool; 16],
+
+    /// The keys as they were last reported by `filter`
+    pressed: [bool; 16],
+
+    /// Keys whose press was ignored. They stay up until released, however long they're held
+    ignored: [bool; 16],
+    released_at: [Option<Duration>; 16]
+}
+
+impl<C: Clock> Debouncer<C> {
+    /// Ignores presses within `window` of the previous release of the same key, whether that
+    /// press was ignored too or not
+    pub fn new(clock: C, window: Duration) -> Debouncer<C> {
+        Debouncer {
+            clock,
+            window,
+            held: [false; 16],
+            pressed: [false; 16],
+            ignored: [false; 16],
+            released_at: [None; 16]
+        }
+    }
+
+    /// The keypad with the keys pressed too soon left up until they are released again
+    pub fn filter(&mut self, keys: [bool; 16]) -> [bool; 16] {
+        let now = self.clock.now();
+
+        for (key, &held) in keys.iter().enumerate() {
+            if held && !self.pressed[key] && !self.ignored[key] {
+                self.pressed[key] = self.released_at[key].is_none_or(|at| now - at >= self.window);
+                self.ignored[key] = !self.pressed[key];
+            }
+            else if !held {
+                if self.held[key] {
+                    self.released_at[key] = Some(now);
+                }
+                self.pressed[key] = false;
+                self.ignored[key] = false;
+            }
+        }
+        self.held = keys;
+
+        self.pressed
+    }
+}
+
+// https://github.com/starrhorne/chip8-rust/blob/master/src/drivers/input_driver.rs
+
+/// Toggles pausing the emulation
+pub const PAUSE_KEY: Keycode = Keycode::P;
+
+/// Restarts the loaded ROM
+pub const RESET_KEY: Keycode = Keycode::Backspace;
+
+/// Executes a single opcode while paused
+pub const STEP_KEY: Keycode = Keycode::N;
+
+/// Saves a screenshot
+pub const SCREENSHOT_KEY: Keycode = Keycode::F12;
+
+/// Starts recording a GIF, or stops and saves the recording
+pub const RECORD_KEY: Keycode = Keycode::F11;
+
+/// Swaps the foreground and background colors
+pub const INVERT_KEY: Keycode = Keycode::I;
+
+/// Shows or hides the grid lines between pixels
+pub const GRID_KEY: Keycode = Keycode::G;
+
+/// Shows or hides the measured frames and opcodes per second
+pub const RATES_KEY: Keycode = Keycode::F3;
+
+/// Runs the emulation in fast forward while held
+pub const TURBO_KEY: Keycode = Keycode::Tab;
+
+/// What the user did since the last poll
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    /// The window was closed
+    Quit,
+
+    /// The reset key was pressed
+    Reset,
+
+    /// The pause key was pressed
+    Pause,
+
+    /// The step key was pressed
+    Step,
+
+    /// The screenshot key was pressed
+    Screenshot,
+
+    /// The record key was pressed
+    Record,
+
+    /// The invert key was pressed
+    Invert,
+
+    /// The rates key was pressed
+    Rates,
+
+    /// The grid key was pressed
+    Grid,
+
+    /// The window lost focus. Only reported if pausing on focus loss is enabled
+    FocusLost,
+
+    /// The window regained focus. Only reported if pausing on focus loss is enabled
+    FocusGained,
+
+    /// No hotkey was pressed. Holds the state of the chip-8 keypad and whether the turbo key is held
+    Keypad { keys: [bool; 16], turbo: bool },
+}
 
 pub struct InputDriver {
     events: sdl2::EventPump,
+    keymap: KeyMap,
+    controllers: GameControllerSubsystem,
+    controller: Option<GameController>,
+    buttonmap: ButtonMap,
+    pause_on_focus_loss: bool,
+    debouncer: Option<Debouncer<SystemClock>>,
 }
 
 impl InputDriver {
-    pub fn new(sdl_context: &sdl2::Sdl) -> Self {
-        InputDriver { events: sdl_context.event_pump().unwrap() }
+    /// Reads the keyboard through `keymap` and the first connected game controller through `buttonmap`.
+    /// Controllers can be plugged in and out while running
+    pub fn new(sdl_context: &sdl2::Sdl, keymap: KeyMap, buttonmap: ButtonMap) -> Self {
+        let controllers = sdl_context.game_controller().unwrap();
+        let controller = first_controller(&controllers);
+
+        InputDriver {
+            events: sdl_context.event_pump().unwrap(),
+            keymap,
+            controllers,
+            controller,
+            buttonmap,
+            pause_on_focus_loss: true,
+            debouncer: None,
+        }
+    }
+
+    /// Sets whether focus changes of the window are reported, so the frontend can pause while
+    /// the window is in the background. Enabled by default
+    pub fn set_pause_on_focus_loss(&mut self, enabled: bool) {
+        self.pause_on_focus_loss = enabled;
     }
 
+    /// Ignores chip-8 keys pressed again within `window` of being released, or stops doing so
+    /// if `window` is `None`. Off by default
+    pub fn set_debounce(&mut self, window: Option<Duration>) {
+        self.debouncer = window.map(|window| Debouncer::new(SystemClock::new(), window));
+    }
 
-    pub fn poll(&mut self) -> Result<[bool; 16], ()> {
+    /// Returns the first quit or hotkey event since the last poll, or the keypad state if there's none.
+    /// The keypad is read from the whole keyboard state rather than key events, so every held key
+    /// is reported. Events after a hotkey are left for the next poll
+    pub fn poll(&mut self) -> InputEvent {
 
         for event in self.events.poll_iter() {
-            if let Event::Quit { .. } = event {
-                return Err(());
-            };
+            match event {
+                Event::Quit { .. } => return InputEvent::Quit,
+                Event::Window { win_event: WindowEvent::FocusLost, .. } if self.pause_on_focus_loss => {
+                    return InputEvent::FocusLost;
+                }
+                Event::Window { win_event: WindowEvent::FocusGained, .. } if self.pause_on_focus_loss => {
+                    return InputEvent::FocusGained;
+                }
+                Event::ControllerDeviceAdded { which, .. } if self.controller.is_none() => {
+                    self.controller = self.controllers.open(which as u32).ok();
+                }
+                Event::ControllerDeviceRemoved { which, .. }
+                    if self.controller.as_ref().map(|c| c.instance_id()) == Some(which) =>
+                {
+                    self.controller = None;
+                    self.controller = first_controller(&self.controllers);
+                }
+                Event::KeyDown { keycode: Some(keycode), repeat: false, .. } => {
+                    match keycode {
+                        PAUSE_KEY => return InputEvent::Pause,
+                        RESET_KEY => return InputEvent::Reset,
+                        STEP_KEY => return InputEvent::Step,
+                        SCREENSHOT_KEY => return InputEvent::Screenshot,
+                        RECORD_KEY => return InputEvent::Record,
+                        INVERT_KEY => return InputEvent::Invert,
+                        RATES_KEY => return InputEvent::Rates,
+                        GRID_KEY => return InputEvent::Grid,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
         }
 
         let keys: Vec<Keycode> = self.events
@@ -28,34 +316,29 @@ impl InputDriver {
             .filter_map(Keycode::from_scancode)
             .collect();
 
-        let mut chip8_keys = [false; 16];
+        let turbo = keys.contains(&TURBO_KEY);
+        let mut chip8_keys = self.keymap.keypad(keys);
 
-        for key in keys {
-            let index = match key {
-                Keycode::Num1 => Some(0x1),
-                Keycode::Num2 => Some(0x2),
-                Keycode::Num3 => Some(0x3),
-                Keycode::Num4 => Some(0xc),
-                Keycode::Q => Some(0x4),
-                Keycode::W => Some(0x5),
-                Keycode::E => Some(0x6),
-                Keycode::R => Some(0xd),
-                Keycode::A => Some(0x7),
-                Keycode::S => Some(0x8),
-                Keycode::D => Some(0x9),
-                Keycode::F => Some(0xe),
-                Keycode::Z => Some(0xa),
-                Keycode::X => Some(0x0),
-                Keycode::C => Some(0xb),
-                Keycode::V => Some(0xf),
-                _ => None,
-            };
-
-            if let Some(i) = index {
-                chip8_keys[i] = true;
+        if let Some(controller) = &self.controller {
+            let buttons = self.buttonmap.pairs().map(|(button, _)| button).filter(|&button| controller.button(button));
+            for (key, &held) in chip8_keys.iter_mut().zip(self.buttonmap.keypad(buttons).iter()) {
+                *key |= held;
             }
         }
 
-        Ok(chip8_keys)
+        if let Some(debouncer) = self.debouncer.as_mut() {
+            chip8_keys = debouncer.filter(chip8_keys);
+        }
+
+        InputEvent::Keypad { keys: chip8_keys, turbo }
     }
-}
\ No newline at end of file
+}
+
+/// Opens the first connected game controller, if any
+fn first_controller(controllers: &GameControllerSubsystem) -> Option<GameController> {
+    let count = controllers.num_joysticks().unwrap_or(0);
+
+    (0..count)
+        .filter(|&id| controllers.is_game_controller(id))
+        .find_map(|id| controllers.open(id).ok())
+}
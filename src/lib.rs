@@ -0,0 +1,52 @@
+//! The chip-8 emulator core. It doesn't depend on SDL, so it can be driven by any frontend.
+//! The SDL audio, display and input drivers are only built with the `sdl` feature, and the
+//! terminal display and input with the `terminal` feature.
+//!
+//! Without the `std` feature only the processor and what it needs are built, under `no_std`
+//! with `alloc`, so the vm can run on microcontrollers
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod processor;
+pub mod font;
+pub mod output;
+pub mod quirks;
+pub mod snapshot;
+pub mod rewind;
+pub mod watch;
+
+#[cfg(feature = "std")]
+pub mod cartridge;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod disasm;
+#[cfg(feature = "std")]
+pub mod assembler;
+#[cfg(feature = "std")]
+pub mod headless;
+#[cfg(feature = "std")]
+pub mod timing;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod menu;
+
+#[cfg(feature = "sdl")]
+pub mod audio;
+#[cfg(feature = "sdl")]
+pub mod display;
+#[cfg(feature = "sdl")]
+pub mod input;
+#[cfg(feature = "sdl")]
+pub mod recorder;
+#[cfg(feature = "terminal")]
+pub mod terminal;
+
+#[cfg(feature = "std")]
+pub use cartridge::Cartridge;
+pub use font::FONT_SET;
+pub use output::ProcessorState;
+pub use processor::Processor;
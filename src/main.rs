@@ -1,40 +1,402 @@
-mod processor;
-mod font;
-mod cartridge;
-mod output;
-mod audio;
-mod display;
-mod input;
+use chipvm::{audio, cartridge, config, display, input, menu, processor, quirks, recorder, replay, timing};
+use input::InputEvent;
+
+const USAGE: &str = "Usage: chipvm [ROM] [--config FILE] [--platform NAME] [--record FILE] [--replay FILE]";
+
+const HELP: &str = "Runs a chip-8, SUPER-CHIP or XO-CHIP ROM. Without one, the ROMs in roms/ or games/
+are listed to pick from with 2, 8 and 5 on the keypad. Use - to read the ROM from standard input
+
+Options:
+    --config FILE    read the settings from FILE instead of chipvm.toml
+    --platform NAME  follow the quirks of vip, chip48, superchip or xochip
+    --record FILE    save the keys pressed in every frame to FILE
+    --replay FILE    play back a session saved with --record
+    --help           show this help
+
+Keys:
+    P          pause and resume
+    N          execute a single instruction while paused
+    Backspace  restart the game
+    Tab        fast forward while held
+    I          swap the foreground and background colors
+    G          show lines between the pixels
+    F3         show the frames and instructions per second in the title bar
+    F11        start and stop recording a GIF
+    F12        save a screenshot";
 
 fn main() {
-    let sleep_duration = std::time::Duration::from_millis(2);
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}\n\n{}", USAGE, HELP);
+        return;
+    }
+    let mut boot_menu = None;
+    let cartridge_filename = match args.get(1) {
+        Some(filename) if !filename.starts_with("--") => Some(filename.clone()),
+        _ => match menu::BootMenu::scan() {
+            Some(menu) => {
+                boot_menu = Some(menu);
+                None
+            }
+            None => {
+                eprintln!("{}\nRun with --help for more information", USAGE);
+                std::process::exit(2);
+            }
+        }
+    };
+
+    // `--record FILE` saves the keypad of every frame and `--replay FILE` plays such a file back
+    let option = |name: &str| args.iter().position(|arg| arg == name).and_then(|i| args.get(i + 1));
+
+    // `--config FILE` reads the settings from FILE instead of chipvm.toml, which has to exist then
+    let config = match option("--config") {
+        Some(path) => config::Config::load(path),
+        None => config::Config::load_or_default(config::DEFAULT_CONFIG_PATH)
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Couldn't load config: {}", e);
+            std::process::exit(1);
+        }
+    };
+    // `--platform NAME` replaces the configured quirks and those of known games
+    let platform = option("--platform").map(|name| match quirks::Platform::from_name(name) {
+        Some(platform) => platform,
+        None => {
+            eprintln!("Unknown platform {}, expected one of {}", name, quirks::PLATFORM_NAMES.join(", "));
+            std::process::exit(2);
+        }
+    });
+    let keymap = if config.keys.is_empty() {
+        input::KeyMap::default()
+    }
+    else {
+        match input::KeyMap::from_names(&config.keys) {
+            Ok(keymap) => keymap,
+            Err(name) => {
+                eprintln!("Couldn't load config: unknown key {}", name);
+                std::process::exit(1);
+            }
+        }
+    };
+    let mut input_player = option("--replay").map(|path| match replay::InputPlayer::replay_from(path) {
+        Ok(player) => player,
+        Err(e) => {
+            eprintln!("Couldn't read replay {}: {}", path, e);
+            std::process::exit(1);
+        }
+    });
+    let seed = match &input_player {
+        Some(player) => player.seed(),
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+    };
+    let mut input_recorder = option("--record").map(|path| match replay::InputRecorder::record_to(path, seed) {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            eprintln!("Couldn't create recording {}: {}", path, e);
+            std::process::exit(1);
+        }
+    });
 
     let sdl_context = sdl2::init().unwrap();
-    let args: Vec<String> = std::env::args().collect();
-    let cartridge_filename = &args[1];
+    let mut audio_driver = audio::Audio::new(&sdl_context);
+    audio_driver.set_volume(config.volume);
+    let canvas = display::open_window(&sdl_context, config.scale);
+    let texture_creator = canvas.texture_creator();
+    let mut display_driver = display::DisplayDriver::new(canvas, &texture_creator, config.scale);
+    let [r, g, b] = config.fg_color;
+    let fg = sdl2::pixels::Color::RGB(r, g, b);
+    let [r, g, b] = config.bg_color;
+    display_driver.set_colors(fg, sdl2::pixels::Color::RGB(r, g, b));
+    let [r, g, b] = config.grid_color;
+    display_driver.set_grid_color(sdl2::pixels::Color::RGB(r, g, b));
+    display_driver.set_persistence(config.persistence);
+    let mut input_driver = input::InputDriver::new(
+        &sdl_context,
+        keymap,
+        input::ButtonMap::default()
+    );
+    if config.debounce_ms > 0 {
+        input_driver.set_debounce(Some(std::time::Duration::from_millis(config.debounce_ms)));
+    }
+    input_driver.set_pause_on_focus_loss(config.pause_on_focus_loss);
+
+    let cartridge_filename = match (cartridge_filename, boot_menu) {
+        (Some(filename), _) => filename,
+        (None, Some(menu)) => match pick_rom(menu, &mut display_driver, &mut input_driver) {
+            Some(filename) => filename,
+            None => return
+        },
+        (None, None) => unreachable!()
+    };
+    let cartridge_driver = match cartridge::Cartridge::open(&cartridge_filename) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Couldn't read ROM {}: {}", cartridge_filename, e);
+            std::process::exit(1);
+        }
+    };
+    let mut processor = processor::Processor::with_seed(seed);
+    // ROMs too large for the classic memory are XO-CHIP ROMs, which get the whole 64K
+    if cartridge_driver.rom.len() > processor::MEMORY_SIZE - processor::PROGRAM_START {
+        processor.memory.resize(processor::XO_CHIP_MEMORY_SIZE, 0);
+    }
+    // Known games get the quirks they need, unless the config asks for others
+    if let Some(info) = cartridge::detect(&cartridge_driver.rom) {
+        println!("Detected {}", info.name);
+        processor.quirks = info.quirks;
+    }
+    if let Some(quirks) = config.quirks {
+        processor.quirks = quirks;
+    }
+    if let Some(platform) = platform {
+        processor.quirks = quirks::Quirks::for_platform(platform);
+    }
+
+    if config.watchdog_frames > 0 {
+        processor.set_watchdog(Some(config.watchdog_frames));
+    }
+
+    if let Err(e) = processor.load_program(&cartridge_driver.rom) {
+        eprintln!("Couldn't load ROM {}: {}", cartridge_filename, e);
+        std::process::exit(1);
+    }
 
-    let audio_driver = audio::Audio::new(&sdl_context);
-    let cartridge_driver = cartridge::Cartridge::read(&cartridge_filename);
-    let mut display_driver = display::DisplayDriver::new(&sdl_context);
-    let mut input_driver = input::InputDriver::new(&sdl_context);
-    let mut processor = processor::Processor::new();
+    let mut gif_recorder = recorder::GifRecorder::new(recorder::DEFAULT_MAX_FRAMES);
+    let mut paused = false;
+    // Emulation is also paused while the window is in the background
+    let mut focused = true;
+    let mut frame_timer = timing::FrameTimer::new(timing::SystemClock::new());
+    let mut throttle = timing::IpsThrottle::new(config.target_ips);
+    let mut rate_counter = timing::RateCounter::new(timing::SystemClock::new());
+    let mut show_rates = false;
+    let turbo_factor = timing::DEFAULT_TURBO_FACTOR;
+    let mute_turbo = true;
+    let mut turbo = false;
+    let mut stuck = false;
+    let mut last_unknown_opcode = None;
 
-    processor.load_program(cartridge_driver.rom);
+    'emulation: loop {
+        // Frames that are due while paused are dropped
+        let frames = frame_timer.frames_due();
+        // Only the keypad of each frame is recorded, so hotkeys that change what the vm runs
+        // would make a replay diverge from its recording
+        let in_session = input_recorder.is_some() || input_player.is_some();
 
-    while let Ok(keypad) = input_driver.poll() {
-        let output = processor.tick(keypad);
+        // Keypad to run the frames with, or None to step a single opcode
+        let (frames, keypad) = match input_driver.poll() {
+            InputEvent::Quit => break,
+            InputEvent::Reset | InputEvent::Pause if in_session => {
+                display_driver.show_toast("Not while recording or replaying");
+                continue;
+            }
+            InputEvent::Reset => {
+                processor.reset();
+                display_driver.show_toast("Reset");
+                continue;
+            }
+            InputEvent::Pause => {
+                paused = !paused;
+                audio_driver.stop_beep();
+                display_driver.show_toast(if paused { "Paused" } else { "Resumed" });
+                continue;
+            }
+            InputEvent::Screenshot => {
+                save_screenshot(&mut display_driver, &processor);
+                continue;
+            }
+            InputEvent::FocusLost => {
+                focused = false;
+                audio_driver.stop_beep();
+                continue;
+            }
+            InputEvent::FocusGained => {
+                focused = true;
+                continue;
+            }
+            InputEvent::Record => {
+                toggle_recording(&mut gif_recorder, &mut display_driver);
+                continue;
+            }
+            InputEvent::Rates => {
+                show_rates = !show_rates;
+                display_driver.show_rates(if show_rates { Some(rate_counter.rates()) } else { None });
+                display_driver.show_toast(if show_rates { "Rates on" } else { "Rates off" });
+                continue;
+            }
+            InputEvent::Grid => {
+                display_driver.set_grid(!display_driver.grid());
+                display_driver.show_toast(if display_driver.grid() { "Grid on" } else { "Grid off" });
+                display_driver.draw(&processor.vram, processor.hires);
+                continue;
+            }
+            InputEvent::Invert => {
+                display_driver.set_invert(!display_driver.invert());
+                display_driver.show_toast(if display_driver.invert() { "Inverted" } else { "Normal colors" });
+                display_driver.draw(&processor.vram, processor.hires);
+                continue;
+            }
+            InputEvent::Step if paused => (1, None),
+            InputEvent::Keypad { keys: keypad, turbo: turbo_held } if !paused && focused => {
+                let turbo_held = turbo_held && !in_session;
+                if turbo_held != turbo {
+                    display_driver.show_toast(if turbo_held { "Fast forward" } else { "Normal speed" });
+                }
+                turbo = turbo_held;
+                (frames, Some(keypad))
+            }
+            _ => (0, None)
+        };
+        let speedup = if turbo { turbo_factor } else { 1 };
 
-        if output.vram_changed {
-            display_driver.draw(&output.vram);
+        for _ in 0..frames {
+            let output = match keypad {
+                None => processor.step(),
+                Some(keypad) => {
+                    let keypad = match input_player.as_mut() {
+                        Some(player) => player.next_frame(),
+                        None => keypad
+                    };
+                    if let Some(recorder) = input_recorder.as_mut() {
+                        if let Err(e) = recorder.record(keypad) {
+                            eprintln!("Couldn't write recording: {}", e);
+                            input_recorder = None;
+                        }
+                    }
+
+                    processor.set_speed(throttle.cycles_for_frame() * speedup);
+                    processor.tick(keypad)
+                }
+            };
+
+            if let Some(e) = output.error {
+                eprintln!("Emulation stopped at {:#05x}: {}", processor.pc, e);
+                break 'emulation;
+            }
+            if output.halted {
+                break 'emulation;
+            }
+            // Undecoded opcodes are skipped, and reported the first time they're found
+            if let Some((pc, opcode)) = output.unknown_opcode.filter(|&found| Some(found) != last_unknown_opcode) {
+                eprintln!("Skipped unknown opcode {:#06X} at {:#05X}", opcode, pc);
+                last_unknown_opcode = Some((pc, opcode));
+            }
+            // Reported once per loop, the vm carries on in case it's just waiting for something
+            if output.stuck && !stuck {
+                eprintln!("Possible infinite loop, pc hasn't moved for {} frames", config.watchdog_frames);
+                display_driver.show_toast("Possible infinite loop");
+            }
+            stuck = output.stuck;
+
+            if let Some(rates) = rate_counter.frame(output.cycles) {
+                if show_rates {
+                    display_driver.show_rates(Some(rates));
+                }
+            }
+
+            if output.vram_changed || display_driver.is_fading() || display_driver.has_toast() {
+                display_driver.draw(output.vram, output.hires);
+            }
+
+            gif_recorder.record_frame(output.vram, output.hires);
+
+            audio_driver.set_pattern(output.audio_pattern, output.audio_pitch);
+
+            if output.beep && !paused && !(turbo && mute_turbo) {
+                audio_driver.start_beep();
+            }
+            else {
+                audio_driver.stop_beep();
+            }
         }
 
-        if output.beep {
-            audio_driver.start_beep();
+        // Toasts are still shown and removed while no frames run, e.g. while paused
+        if frames == 0 && display_driver.has_toast() {
+            display_driver.draw(&processor.vram, processor.hires);
         }
-        else {
-            audio_driver.stop_beep();
+
+        std::thread::sleep(frame_timer.until_next_frame());
+    }
+
+    audio_driver.stop_beep();
+
+    if let Some(recorder) = input_recorder {
+        if let Err(e) = recorder.finish() {
+            eprintln!("Couldn't write recording: {}", e);
+        }
+    }
+}
+
+/// Shows the boot menu until a ROM is picked. Returns `None` if the window is closed instead
+fn pick_rom(
+    mut boot_menu: menu::BootMenu,
+    display_driver: &mut display::DisplayDriver<'_>,
+    input_driver: &mut input::InputDriver
+) -> Option<String> {
+    loop {
+        match input_driver.poll() {
+            InputEvent::Quit => return None,
+            InputEvent::Keypad { keys, .. } => {
+                if let Some(filename) = boot_menu.update(keys) {
+                    return Some(filename.to_string());
+                }
+            }
+            _ => {}
         }
 
-        std::thread::sleep(sleep_duration);
+        display_driver.draw(&boot_menu.render(), false);
+        std::thread::sleep(timing::FRAME_DURATION);
+    }
+}
+
+/// A file name made of the prefix and the current time
+fn timestamped_path(prefix: &str, extension: &str) -> String {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    format!("{}-{}.{}", prefix, timestamp, extension)
+}
+
+/// Saves the screen to a PNG named after the current time
+fn save_screenshot(display_driver: &mut display::DisplayDriver<'_>, processor: &processor::Processor) {
+    let path = timestamped_path("screenshot", "png");
+
+    match display_driver.screenshot(&processor.vram, processor.hires, &path) {
+        Ok(()) => {
+            println!("Saved screenshot to {}", path);
+            display_driver.show_toast(&format!("Saved {}", path));
+        }
+        Err(e) => {
+            eprintln!("Couldn't save screenshot to {}: {}", path, e);
+            display_driver.show_toast("Screenshot failed");
+        }
+    }
+}
+
+/// Starts recording, or stops and saves the recording to a GIF named after the current time
+fn toggle_recording(gif_recorder: &mut recorder::GifRecorder, display_driver: &mut display::DisplayDriver<'_>) {
+    if !gif_recorder.is_recording() {
+        gif_recorder.start();
+        println!("Started recording");
+        display_driver.show_toast("Recording");
+        return;
+    }
+
+    let path = timestamped_path("recording", "gif");
+    match gif_recorder.stop(&path, display_driver.scale(), display_driver.palette()) {
+        Ok(()) => {
+            println!("Saved recording to {}", path);
+            display_driver.show_toast(&format!("Saved {}", path));
+        }
+        Err(e) => {
+            eprintln!("Couldn't save recording to {}: {}", path, e);
+            display_driver.show_toast("Recording failed");
+        }
     }
-}
\ No newline at end of file
+}
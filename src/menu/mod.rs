@@ -0,0 +1,164 @@
+use crate::font::{font_sprite, FONT_HEIGHT};
+use crate::output::{Vram, VRAM_WIDTH, VRAM_HEIGHT};
+
+/// Directories searched for ROMs to list, in order. The first one with any files in it is used
+pub const ROM_DIRS: [&str; 2] = ["roms", "games"];
+
+/// Glyphs for the letters past F and a few symbols, in the style of `FONT_SET`. Digits and the
+/// letters A to F come from `FONT_SET` itself
+const EXTRA_GLYPHS: [(char, [u8; FONT_HEIGHT]); 26] = [
+    ('G', [0xF0, 0x80, 0xB0, 0x90, 0xF0]),
+    ('H', [0x90, 0x90, 0xF0, 0x90, 0x90]),
+    ('I', [0xE0, 0x40, 0x40, 0x40, 0xE0]),
+    ('J', [0x10, 0x10, 0x10, 0x90, 0xF0]),
+    ('K', [0x90, 0xA0, 0xC0, 0xA0, 0x90]),
+    ('L', [0x80, 0x80, 0x80, 0x80, 0xF0]),
+    ('M', [0x90, 0xF0, 0xF0, 0x90, 0x90]),
+    ('N', [0x90, 0xD0, 0xB0, 0x90, 0x90]),
+    ('O', [0xF0, 0x90, 0x90, 0x90, 0xF0]),
+    ('P', [0xF0, 0x90, 0xF0, 0x80, 0x80]),
+    ('Q', [0xF0, 0x90, 0x90, 0xB0, 0xF0]),
+    ('R', [0xF0, 0x90, 0xF0, 0xA0, 0x90]),
+    ('S', [0xF0, 0x80, 0xF0, 0x10, 0xF0]),
+    ('T', [0xF0, 0x40, 0x40, 0x40, 0x40]),
+    ('U', [0x90, 0x90, 0x90, 0x90, 0xF0]),
+    ('V', [0x90, 0x90, 0x90, 0x90, 0x60]),
+    ('W', [0x90, 0x90, 0xF0, 0xF0, 0x90]),
+    ('X', [0x90, 0x90, 0x60, 0x90, 0x90]),
+    ('Y', [0x90, 0x90, 0x60, 0x40, 0x40]),
+    ('Z', [0xF0, 0x10, 0x20, 0x40, 0xF0]),
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('-', [0x00, 0x00, 0xF0, 0x00, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0xF0]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x40]),
+    ('>', [0x80, 0x40, 0x20, 0x40, 0x80]),
+    ('?', [0xF0, 0x10, 0x60, 0x00, 0x40]),
+];
+
+/// Width of a character cell, i.e. a glyph and the gap after it
+pub(crate) const CELL_WIDTH: usize = 5;
+
+/// Height of a line, i.e. a glyph and the gap under it
+pub(crate) const LINE_HEIGHT: usize = FONT_HEIGHT + 1;
+
+/// Lines that fit on the 64*32 screen
+const VISIBLE_LINES: usize = 32 / LINE_HEIGHT;
+
+/// Characters of a name that fit on a line after the cursor
+const NAME_LENGTH: usize = 64 / CELL_WIDTH - 1;
+
+/// Keypad keys that move the selection up and down and pick the selected ROM
+const UP_KEY: usize = 0x2;
+const DOWN_KEY: usize = 0x8;
+const PICK_KEY: usize = 0x5;
+
+/// A list of ROMs drawn with the chip-8 font and navigated with the keypad: 2 and 8 move the
+/// selection and 5 picks the selected ROM
+pub struct BootMenu {
+    entries: Vec<String>,
+    selected: usize,
+    top: usize,
+    last_keypad: [bool; 16]
+}
+
+impl BootMenu {
+    pub fn new(entries: Vec<String>) -> BootMenu {
+        BootMenu {
+            entries,
+            selected: 0,
+            top: 0,
+            last_keypad: [false; 16]
+        }
+    }
+
+    /// Lists the files in the first of `ROM_DIRS` that has any, sorted by name. Returns `None`
+    /// if there's none
+    pub fn scan() -> Option<BootMenu> {
+        ROM_DIRS.iter().find_map(|dir| {
+            let mut entries: Vec<String> = std::fs::read_dir(dir)
+                .ok()?
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_file())
+                .filter_map(|entry| entry.path().to_str().map(str::to_string))
+                .collect();
+            entries.sort();
+
+            if entries.is_empty() { None } else { Some(BootMenu::new(entries)) }
+        })
+    }
+
+    /// Handles keys pressed since the last update. Returns the picked entry once one is picked
+    pub fn update(&mut self, keypad: [bool; 16]) -> Option<&str> {
+        let pressed = |key: usize| keypad[key] && !self.last_keypad[key];
+        let (up, down, pick) = (pressed(UP_KEY), pressed(DOWN_KEY), pressed(PICK_KEY));
+        self.last_keypad = keypad;
+
+        if up && self.selected > 0 {
+            self.selected -= 1;
+        }
+        if down && self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+
+        // Scroll so the selection stays visible
+        if self.selected < self.top {
+            self.top = self.selected;
+        }
+        if self.selected >= self.top + VISIBLE_LINES {
+            self.top = self.selected + 1 - VISIBLE_LINES;
+        }
+
+        if pick {
+            self.entries.get(self.selected).map(String::as_str)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Draws the visible part of the list into low resolution vram, with a cursor before the
+    /// selected entry. Names are shown without their directory and cut to fit a line
+    pub fn render(&self) -> Vram {
+        let mut vram = [[0; VRAM_WIDTH]; VRAM_HEIGHT];
+
+        for (line, entry) in self.entries.iter().enumerate().skip(self.top).take(VISIBLE_LINES) {
+            let y = (line - self.top) * LINE_HEIGHT;
+            if line == self.selected {
+                draw_char(&mut vram, '>', 0, y);
+            }
+
+            let name = entry.rsplit('/').next().unwrap_or(entry);
+            for (column, c) in name.chars().take(NAME_LENGTH).enumerate() {
+                draw_char(&mut vram, c, (column + 1) * CELL_WIDTH, y);
+            }
+        }
+
+        vram
+    }
+}
+
+/// The glyph for the character. Lowercase letters use the uppercase glyphs and unknown
+/// characters are drawn as `?`
+pub(crate) fn glyph(c: char) -> &'static [u8] {
+    let c = c.to_ascii_uppercase();
+
+    match c.to_digit(16) {
+        Some(digit) => font_sprite(digit as u8),
+        None => EXTRA_GLYPHS
+            .iter()
+            .find(|(glyph_char, _)| *glyph_char == c)
+            .or_else(|| EXTRA_GLYPHS.iter().find(|(glyph_char, _)| *glyph_char == '?'))
+            .map(|(_, rows)| &rows[..])
+            .unwrap_or(&[])
+    }
+}
+
+fn draw_char(vram: &mut Vram, c: char, x: usize, y: usize) {
+    for (row, &byte) in glyph(c).iter().enumerate() {
+        for bit in 0..4 {
+            if byte & (0x80 >> bit) != 0 {
+                vram[y + row][x + bit] = 1;
+            }
+        }
+    }
+}
@@ -1,5 +1,58 @@
-pub struct ProcessorState {
-    pub vram: [[u8; 64]; 32],
+use crate::processor::EmuError;
+use crate::watch::WatchHit;
+
+/// Width of the vram. Large enough for the 128*64 SUPER-CHIP high resolution mode
+pub const VRAM_WIDTH: usize = 128;
+
+/// Height of the vram. Large enough for the 128*64 SUPER-CHIP high resolution mode
+pub const VRAM_HEIGHT: usize = 64;
+
+/// The vram of the vm. Only the top left 64*32 pixels are used in low resolution mode.
+/// Every pixel holds one bit per XO-CHIP plane, so it ranges from 0 to 3
+pub type Vram = [[u8; VRAM_WIDTH]; VRAM_HEIGHT];
+
+/// What the vm looks like after a `tick` or `step`. Borrows the vram from the processor
+/// instead of copying it every frame
+pub struct ProcessorState<'a> {
+    pub vram: &'a Vram,
     pub vram_changed: bool,
-    pub beep: bool
-}
\ No newline at end of file
+    pub beep: bool,
+
+    /// How many opcodes were executed
+    pub cycles: usize,
+
+    /// Set if the vm is in the 128*64 high resolution mode
+    pub hires: bool,
+
+    /// Width and height of the active display. 64*32 unless the vm is in high resolution mode
+    pub resolution: (u16, u16),
+
+    /// Set if the most recent DXYN erased any pixel
+    pub collision: bool,
+
+    /// The error that stopped the vm, if any
+    pub error: Option<EmuError>,
+
+    /// Set once the program exited with 00FD. The frontend should stop running it
+    pub halted: bool,
+
+    /// The breakpoint execution halted at, if any. The opcode there hasn't been executed yet
+    pub hit_breakpoint: Option<usize>,
+
+    /// The watched register or memory byte that changed, if any. Execution halted right after
+    /// the opcode that changed it
+    pub hit_watch: Option<WatchHit>,
+
+    /// Set once pc made no progress for as many ticks as the watchdog allows, which likely means
+    /// the program is stuck in an infinite loop. The vm keeps running either way
+    pub stuck: bool,
+
+    /// The XO-CHIP audio pattern to play while beeping, if one was loaded
+    pub audio_pattern: Option<[u8; 16]>,
+
+    /// The XO-CHIP playback rate of the audio pattern
+    pub audio_pitch: u8,
+
+    /// pc and opcode of the last opcode the processor didn't decode, unless those are skipped
+    pub unknown_opcode: Option<(usize, u16)>
+}
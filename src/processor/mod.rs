@@ -1,16 +1,198 @@
-use crate::output::ProcessorState;
-use crate::font::FONT_SET;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeMap as HashMap, BTreeSet as HashSet};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+use crate::output::{ProcessorState, Vram, VRAM_WIDTH, VRAM_HEIGHT};
+use crate::font;
+use crate::quirks::Quirks;
+use crate::rewind::RewindBuffer;
+use crate::watch::{Watch, WatchHit};
+
+/// Size of the memory of the classic chip-8 and SUPER-CHIP
+pub const MEMORY_SIZE: usize = 4096;
+
+/// Size of the memory of XO-CHIP, all of which I can address with F000 NNNN
+pub const XO_CHIP_MEMORY_SIZE: usize = 65536;
+
+/// Address programs are loaded at and start executing from
+pub const PROGRAM_START: usize = 0x200;
+
+/// Address ETI-660 programs are loaded at and start executing from
+pub const ETI660_PROGRAM_START: usize = 0x600;
+
+/// How many subroutine calls can be nested. The original interpreter allowed 12 and SUPER-CHIP 16
+pub const STACK_DEPTH: usize = 16;
+
+/// How many opcodes `step_over` executes at most before giving up on a subroutine returning
+pub const STEP_OVER_LIMIT: usize = 1_000_000;
+
+/// Errors that can happen while loading a program into memory
+#[derive(Debug)]
+pub enum LoadError {
+    /// The program doesn't fit in the memory after the load address
+    TooLarge { len: usize, available: usize },
+
+    /// The load address is outside of memory. Holds the address
+    BadAddress(usize)
+}
+
+impl core::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            LoadError::TooLarge { len, available } => write!(
+                f,
+                "program is {} bytes but only {} bytes of memory are available",
+                len,
+                available
+            ),
+            LoadError::BadAddress(addr) => write!(f, "load address {:#X} is outside of memory", addr)
+        }
+    }
+}
+
+/// Error returned when poking memory outside of the memory of the vm, or setting a
+/// register, I, pc or sp to something out of range. Holds the rejected index or value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutOfBounds(pub usize);
+
+impl core::fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "address {:#X} is out of bounds", self.0)
+    }
+}
+
+/// Errors that can happen while executing a program. The vm stops executing opcodes once one happens
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmuError {
+    /// A subroutine was called with every stack slot already in use
+    StackOverflow,
+
+    /// A subroutine returned with nothing on the stack
+    StackUnderflow,
+
+    /// An opcode the processor doesn't decode was found while following `UnknownOpcodePolicy::Halt`
+    UnknownOpcode { pc: usize, opcode: u16 }
+}
+
+impl core::fmt::Display for EmuError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            EmuError::StackOverflow => write!(f, "stack overflow"),
+            EmuError::StackUnderflow => write!(f, "stack underflow"),
+            EmuError::UnknownOpcode { pc, opcode } => write!(f, "unknown opcode {:#06X} at {:#05X}", opcode, pc)
+        }
+    }
+}
+
+/// What the vm does when it finds an opcode it doesn't decode
+#[derive(Default)]
+pub enum UnknownOpcodePolicy {
+    /// Record pc and the opcode in `ProcessorState::unknown_opcode` for the frontend to show,
+    /// then skip it
+    #[default]
+    Report,
+
+    /// Silently skip it, like most interpreters do
+    Skip,
+
+    /// Stop executing with `EmuError::UnknownOpcode`
+    Halt,
+
+    /// Call the callback with pc and the opcode, then skip it
+    Callback(Box<dyn FnMut(usize, u16)>)
+}
+
+/// The random number generator a new processor starts with. Without `std` there's no entropy
+/// source, so it's seeded with 0 and embedded users should `set_rng` their own
+fn default_rng() -> Box<dyn RngCore> {
+    #[cfg(feature = "std")]
+    return Box::new(StdRng::from_entropy());
+
+    #[cfg(not(feature = "std"))]
+    return Box::new(StdRng::seed_from_u64(0));
+}
+
+/// The opcode family an opcode belongs to, written with its operands as letters like `8XY4` or
+/// `DXYN`. Opcodes the processor doesn't decode belong to `unknown`
+pub fn opcode_class(opcode: u16) -> &'static str {
+    let nibbles = (
+        (opcode & 0xF000) >> 12,
+        (opcode & 0x0F00) >> 8,
+        (opcode & 0x00F0) >> 4,
+        opcode & 0x000F,
+    );
+
+    match nibbles {
+        (0x00, 0x00, 0x0e, 0x00) => "00E0",
+        (0x00, 0x00, 0x0c, _) => "00CN",
+        (0x00, 0x00, 0x0e, 0x0e) => "00EE",
+        (0x00, 0x00, 0x0f, 0x0b) => "00FB",
+        (0x00, 0x00, 0x0f, 0x0c) => "00FC",
+        (0x00, 0x00, 0x0f, 0x0d) => "00FD",
+        (0x00, 0x00, 0x0f, 0x0e) => "00FE",
+        (0x00, 0x00, 0x0f, 0x0f) => "00FF",
+        (0x01, _, _, _) => "1NNN",
+        (0x02, _, _, _) => "2NNN",
+        (0x03, _, _, _) => "3XKK",
+        (0x04, _, _, _) => "4XKK",
+        (0x05, _, _, 0x00) => "5XY0",
+        (0x06, _, _, _) => "6XKK",
+        (0x07, _, _, _) => "7XKK",
+        (0x08, _, _, 0x00) => "8XY0",
+        (0x08, _, _, 0x01) => "8XY1",
+        (0x08, _, _, 0x02) => "8XY2",
+        (0x08, _, _, 0x03) => "8XY3",
+        (0x08, _, _, 0x04) => "8XY4",
+        (0x08, _, _, 0x05) => "8XY5",
+        (0x08, _, _, 0x06) => "8XY6",
+        (0x08, _, _, 0x07) => "8XY7",
+        (0x08, _, _, 0x0e) => "8XYE",
+        (0x09, _, _, 0x00) => "9XY0",
+        (0x0a, _, _, _) => "ANNN",
+        (0x0b, _, _, _) => "BNNN",
+        (0x0c, _, _, _) => "CXKK",
+        (0x0d, _, _, _) => "DXYN",
+        (0x0e, _, 0x09, 0x0e) => "EX9E",
+        (0x0e, _, 0x0a, 0x01) => "EXA1",
+        (0x0f, 0x00, 0x00, 0x00) => "F000",
+        (0x0f, 0x00, 0x00, 0x02) => "F002",
+        (0x0f, _, 0x00, 0x01) => "FX01",
+        (0x0f, _, 0x00, 0x07) => "FX07",
+        (0x0f, _, 0x00, 0x0a) => "FX0A",
+        (0x0f, _, 0x01, 0x05) => "FX15",
+        (0x0f, _, 0x01, 0x08) => "FX18",
+        (0x0f, _, 0x01, 0x0e) => "FX1E",
+        (0x0f, _, 0x02, 0x09) => "FX29",
+        (0x0f, _, 0x03, 0x00) => "FX30",
+        (0x0f, _, 0x03, 0x03) => "FX33",
+        (0x0f, _, 0x03, 0x0a) => "FX3A",
+        (0x0f, _, 0x05, 0x05) => "FX55",
+        (0x0f, _, 0x06, 0x05) => "FX65",
+        (0x0f, _, 0x07, 0x05) => "FX75",
+        (0x0f, _, 0x08, 0x05) => "FX85",
+        _ => "unknown"
+    }
+}
 
 pub struct Processor {
     
-    /// The chip-8 memory. 4096 bytes in size which means it can store 32768 bits of data
-    pub memory: [u8; 4096],
+    /// The chip-8 memory. `MEMORY_SIZE` bytes in size unless the processor was created with
+    /// another size, e.g. `XO_CHIP_MEMORY_SIZE`
+    pub memory: Vec<u8>,
 
     /// The registers of the chip-8 vm. 1 byte in size and there's 16 of them from V0 to VF
     pub registers: [u8; 16],
 
-    /// The stack of chip-8. Stores return addresses when a subroutine is called
-    pub stack: [usize; 48],
+    /// The stack of chip-8. Stores return addresses when a subroutine is called. Calling past
+    /// `STACK_DEPTH` nested subroutines stops the vm with `EmuError::StackOverflow`
+    pub stack: [usize; STACK_DEPTH],
 
     /// The stack pointer. Points to the addr of the last routine
     pub sp: usize,
@@ -21,90 +203,606 @@ pub struct Processor {
     /// Sound timer of chip-8. Counts down at 60Hz and makes buzzer sound until the value is zero
     pub sound_timer: u8,
 
-    /// The vram of chip-8. Contains sprites to display in a 1 byte array with capacity to store 2048 values which represent the 64*32 sized display
-    pub vram: [[u8; 64]; 32],
+    /// The XO-CHIP 128 bit audio pattern loaded by F002. The plain buzzer sounds until one is loaded
+    pub audio_pattern: Option<[u8; 16]>,
+
+    /// The XO-CHIP playback rate of the audio pattern set by FX3A. 64 plays 4000 bits per second
+    pub audio_pitch: u8,
+
+    /// The vram of chip-8. Contains sprites to display in a 1 byte array with capacity to store 8192 values which represent the 128*64 sized display
+    pub vram: Vram,
 
-    /// Waits for keypress when EXA1 opcode is found. Indicates if the vm is actually waiting for a keypress
+    /// Set if the vm is in the SUPER-CHIP 128*64 high resolution mode. Only the top left 64*32 pixels of vram are used otherwise
+    pub hires: bool,
+
+    /// The XO-CHIP bit-planes drawn to and cleared, one bit per plane. Each vram pixel holds
+    /// one bit per plane too, so pixels range from 0 to 3
+    pub planes: u8,
+
+    /// Set while FX0A waits for a keypress. pc stays on the FX0A until the key is released, so
+    /// it's executed again every cycle until then
     pub keypresswait: bool,
 
     /// The key the vm is waiting for. Stored in Vx
     pub key: usize,
 
+    /// The key pressed while waiting for a keypress. The wait ends once it's released
+    pub pressed_key: Option<usize>,
+
     /// The whole keypad
     pub keypad: [bool; 16],
 
     /// Program counter
     pub pc: usize,
 
+    /// Address the program was loaded at and starts executing from
+    pub load_address: usize,
+
     /// Index register pointing to a memory address
     pub i: usize,
 
     /// Set if any pixel is unset from set. Possible use is collision detection
-    pub vram_changed: bool
+    pub vram_changed: bool,
+
+    /// Set if the most recent DXYN erased any pixel, i.e. the VF result of that draw
+    pub collision: bool,
+
+    /// Set by DXYN when following the `display_wait` quirk. No more opcodes execute until the next frame
+    pub vblank_wait: bool,
+
+    /// How many opcodes are executed in a single `tick`
+    pub cycles_per_frame: usize,
+
+    /// Interpreter quirks to follow while executing opcodes
+    pub quirks: Quirks,
+
+    /// The SUPER-CHIP HP-48 RPL user flags. Saved and restored by FX75 and FX85
+    pub rpl_flags: [u8; 8],
+
+    /// The error that stopped the vm, if any
+    pub error: Option<EmuError>,
+
+    /// Set once a SUPER-CHIP 00FD exited the program
+    pub halted: bool,
+
+    /// Addresses execution halts at before executing the opcode there
+    pub breakpoints: HashSet<usize>,
+
+    /// The breakpoint execution halted at during the last `tick` or `step`, if any
+    pub hit_breakpoint: Option<usize>,
+
+    /// Set after halting at a breakpoint so the next `tick` or `step` executes the opcode there
+    pub resume_from_breakpoint: bool,
+
+    /// Registers and memory execution halts on when they change, in the order they were added
+    pub watches: Vec<Watch>,
+
+    /// The watched value that changed during the last `tick` or `step`, if any
+    pub hit_watch: Option<WatchHit>,
+
+    /// Ticks without progress after which a possible infinite loop is reported, or `None` to
+    /// never report one. A tick makes no progress if every opcode it executed was at the same
+    /// address, like a 1NNN jumping to itself
+    pub watchdog: Option<usize>,
+
+    /// Consecutive ticks that made no progress
+    pub stuck_ticks: usize,
+
+    /// Called with pc and the opcode before every opcode is executed
+    pub trace: Option<Box<dyn FnMut(usize, u16)>>,
+
+    /// How many times each opcode class was executed, keyed by `opcode_class`
+    pub opcode_histogram: HashMap<&'static str, u64>,
+
+    /// Time spent executing each opcode class, keyed by `opcode_class`
+    #[cfg(feature = "profiling")]
+    pub opcode_time: HashMap<&'static str, std::time::Duration>,
+
+    /// What to do when an opcode the processor doesn't decode is found
+    pub on_unknown_opcode: UnknownOpcodePolicy,
+
+    /// pc and opcode of the last unknown opcode found, unless unknown opcodes are skipped
+    pub unknown_opcode: Option<(usize, u16)>,
+
+    /// Snapshots of the most recent frames if rewinding is enabled
+    pub rewind_buffer: Option<RewindBuffer>,
+
+    /// Source of the random numbers CXKK draws from
+    pub rng: Box<dyn RngCore>
+}
+
+impl Default for Processor {
+    fn default() -> Processor {
+        Processor::new()
+    }
 }
 
 impl Processor {
     pub fn new() -> Processor {
-        let mut mem: [u8; 4096] = [0; 4096];
-        for x in 0..FONT_SET.len() {
-            mem[x] = FONT_SET[x];
-        }
+        Processor::with_memory_size(MEMORY_SIZE)
+    }
+
+    /// Creates a processor with `size` bytes of memory. Smaller sizes than `MEMORY_SIZE` are
+    /// raised to it, the fonts and the program area have to fit
+    pub fn with_memory_size(size: usize) -> Processor {
+        let mut mem = vec![0; size.max(MEMORY_SIZE)];
+        font::load_fonts(&mut mem);
 
         Processor {
             memory: mem,
             registers: [0; 16],
-            stack: [0; 48],
+            stack: [0; STACK_DEPTH],
             sp: 0,
             delay_timer: 0,
             sound_timer: 0,
-            vram: [[0; 64]; 32],
+            audio_pattern: None,
+            audio_pitch: 64,
+            vram: [[0; VRAM_WIDTH]; VRAM_HEIGHT],
+            hires: false,
+            planes: 1,
             keypresswait: false,
             key: 0,
-            pc: 0x200,
+            pressed_key: None,
+            pc: PROGRAM_START,
+            load_address: PROGRAM_START,
             i: 0,
             vram_changed: false,
-            keypad: [false; 16]
+            collision: false,
+            vblank_wait: false,
+            keypad: [false; 16],
+            cycles_per_frame: 10,
+            quirks: Quirks::default(),
+            rpl_flags: [0; 8],
+            error: None,
+            halted: false,
+            breakpoints: HashSet::new(),
+            hit_breakpoint: None,
+            resume_from_breakpoint: false,
+            watches: Vec::new(),
+            hit_watch: None,
+            watchdog: None,
+            stuck_ticks: 0,
+            trace: None,
+            opcode_histogram: HashMap::new(),
+            #[cfg(feature = "profiling")]
+            opcode_time: HashMap::new(),
+            on_unknown_opcode: UnknownOpcodePolicy::default(),
+            unknown_opcode: None,
+            rewind_buffer: None,
+            rng: default_rng()
         }
     }
 
-    pub fn tick(&mut self, keypad: [bool; 16]) -> ProcessorState {
+    /// Creates a processor that follows the given interpreter quirks
+    pub fn with_quirks(quirks: Quirks) -> Processor {
+        let mut processor = Processor::new();
+        processor.quirks = quirks;
+
+        processor
+    }
+
+    /// Creates a processor whose CXKK draws from a random number generator seeded with `seed`,
+    /// so runs with the same input are reproducible
+    pub fn with_seed(seed: u64) -> Processor {
+        let mut processor = Processor::new();
+        processor.rng = Box::new(StdRng::seed_from_u64(seed));
+
+        processor
+    }
+
+    /// Puts the vm back in its power-on state without discarding the loaded program, so it
+    /// restarts from the load address. Quirks, speed, the RPL user flags, breakpoints, watches,
+    /// the watchdog, the trace callback, the unknown opcode policy, the rewind buffer and the
+    /// random number generator are kept
+    pub fn reset(&mut self) {
+        *self = Processor {
+            memory: core::mem::take(&mut self.memory),
+            pc: self.load_address,
+            load_address: self.load_address,
+            cycles_per_frame: self.cycles_per_frame,
+            quirks: self.quirks,
+            rpl_flags: self.rpl_flags,
+            breakpoints: core::mem::take(&mut self.breakpoints),
+            watches: core::mem::take(&mut self.watches),
+            watchdog: self.watchdog,
+            trace: self.trace.take(),
+            on_unknown_opcode: core::mem::take(&mut self.on_unknown_opcode),
+            rewind_buffer: self.rewind_buffer.take(),
+            rng: core::mem::replace(&mut self.rng, Box::new(StdRng::seed_from_u64(0))),
+            ..Processor::new()
+        };
+        font::load_fonts(&mut self.memory);
+    }
+
+    /// Runs a single frame with the given keypad state.
+    ///
+    /// Executes `cycles_per_frame` opcodes and then counts the delay and sound timers down
+    /// once, so the caller is expected to call this at 60Hz. The frame ends early if a
+    /// breakpoint or watch is hit, when FX0A starts waiting for a key so the frontend can poll
+    /// the keypad, or after a draw when following the `display_wait` quirk
+    pub fn tick(&mut self, keypad: [bool; 16]) -> ProcessorState<'_> {
+        if self.rewind_buffer.is_some() {
+            let state = self.snapshot();
+            if let Some(buffer) = self.rewind_buffer.as_mut() {
+                buffer.push(state);
+            }
+        }
+
         self.keypad = keypad;
         self.vram_changed = false;
+        self.hit_breakpoint = None;
+        self.hit_watch = None;
+        self.vblank_wait = false;
 
-        if self.keypresswait {
-            for i in 0..keypad.len() {
-                if keypad[i] {
-                    self.keypresswait = false;
-                    self.registers[self.key] = i as u8;
-                    break;
-                }
+        let start_pc = self.pc;
+        let mut moved = false;
+        let mut cycles = 0;
+        while cycles < self.cycles_per_frame && self.cycle() {
+            cycles += 1;
+            moved |= self.pc != start_pc;
+            if self.vblank_wait || self.keypresswait || self.hit_watch.is_some() {
+                break;
             }
-        } else {
-            if self.delay_timer > 0 {
-                self.delay_timer -= 1;
+        }
+
+        // Waiting for a key with FX0A keeps pc too, but that's no loop
+        if cycles > 0 && !moved && !self.keypresswait {
+            self.stuck_ticks += 1;
+        }
+        else {
+            self.stuck_ticks = 0;
+        }
+
+        // Beep based on the sound timer this frame ran with, so a sound timer of 1 beeps for exactly one frame
+        let beep = self.sound_timer > 0;
+        self.tick_timers();
+
+        self.state(beep, cycles)
+    }
+
+    /// Like `tick`, but returns the error that stopped the vm instead of reporting it in the state.
+    /// Once an error happened every later call fails with it too, until the vm is reset
+    pub fn try_tick(&mut self, keypad: [bool; 16]) -> Result<ProcessorState<'_>, EmuError> {
+        let state = self.tick(keypad);
+
+        match state.error {
+            Some(e) => Err(e),
+            None => Ok(state)
+        }
+    }
+
+    /// Executes exactly one opcode with the current keypad state, ignoring `cycles_per_frame`
+    /// and leaving the timers alone. Meant for debuggers
+    pub fn step(&mut self) -> ProcessorState<'_> {
+        self.vram_changed = false;
+        self.hit_breakpoint = None;
+        self.hit_watch = None;
+        let cycles = self.cycle() as usize;
+
+        self.state(self.sound_timer > 0, cycles)
+    }
+
+    /// Like `step`, but runs a 2NNN call and everything it calls until it returns, stopping at
+    /// the opcode after the call. Stops early on a breakpoint, a watch, an error, when the
+    /// subroutine waits for a key or the next frame, or after `STEP_OVER_LIMIT` opcodes, e.g.
+    /// when the subroutine never returns
+    pub fn step_over(&mut self) -> ProcessorState<'_> {
+        if self.current_opcode() & 0xF000 != 0x2000 {
+            return self.step();
+        }
+
+        let sp = self.sp;
+        self.vram_changed = false;
+        self.hit_breakpoint = None;
+        self.hit_watch = None;
+        self.vblank_wait = false;
+
+        let mut cycles = 0;
+        while cycles < STEP_OVER_LIMIT && self.cycle() {
+            cycles += 1;
+            if self.sp == sp || self.vblank_wait || self.keypresswait || self.hit_watch.is_some() {
+                break;
             }
-            if self.sound_timer > 0 {
-                self.sound_timer -= 1;
+        }
+
+        self.state(self.sound_timer > 0, cycles)
+    }
+
+    /// The opcode at pc, i.e. the one that gets executed next. Reads wrap around the end of
+    /// memory, so a pc at the very top of memory doesn't panic
+    pub fn current_opcode(&self) -> u16 {
+        let len = self.memory.len();
+        (self.memory[self.pc % len] as u16) << 8 | (self.memory[(self.pc + 1) % len] as u16)
+    }
+
+    /// The byte at `addr`, or `None` if it's outside of memory
+    pub fn read_memory(&self, addr: usize) -> Option<u8> {
+        self.memory.get(addr).copied()
+    }
+
+    /// Sets the byte at `addr`. Fails without touching memory if `addr` is outside of it
+    pub fn write_memory(&mut self, addr: usize, val: u8) -> Result<(), OutOfBounds> {
+        match self.memory.get_mut(addr) {
+            Some(byte) => {
+                *byte = val;
+                Ok(())
             }
-            let opcode = self.get_opcode();
-            self.execute_once(opcode);
+            None => Err(OutOfBounds(addr))
         }
+    }
 
-        ProcessorState {
-            vram: self.vram.clone(),
-            vram_changed: self.vram_changed,
-            beep: self.sound_timer > 0
+    /// The bytes in `range`, or `None` if any of it is outside of memory
+    pub fn memory_slice(&self, range: core::ops::Range<usize>) -> Option<&[u8]> {
+        self.memory.get(range)
+    }
+
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.registers
+    }
+
+    /// The value of Vx. Panics if x is past VF
+    pub fn register(&self, x: usize) -> u8 {
+        self.registers[x]
+    }
+
+    /// Sets Vx. Fails if x is past VF
+    pub fn set_register(&mut self, x: usize, val: u8) -> Result<(), OutOfBounds> {
+        match self.registers.get_mut(x) {
+            Some(register) => {
+                *register = val;
+                Ok(())
+            }
+            None => Err(OutOfBounds(x))
+        }
+    }
+
+    pub fn i(&self) -> usize {
+        self.i
+    }
+
+    /// Points I at `addr`. Fails if it's outside of memory
+    pub fn set_i(&mut self, addr: usize) -> Result<(), OutOfBounds> {
+        if addr >= self.memory.len() {
+            return Err(OutOfBounds(addr));
+        }
+
+        self.i = addr;
+        Ok(())
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Moves pc to `addr`. Fails if it's outside of memory
+    pub fn set_pc(&mut self, addr: usize) -> Result<(), OutOfBounds> {
+        if addr >= self.memory.len() {
+            return Err(OutOfBounds(addr));
+        }
+
+        self.pc = addr;
+        Ok(())
+    }
+
+    pub fn sp(&self) -> usize {
+        self.sp
+    }
+
+    /// The return addresses of the subroutines currently being executed, innermost last
+    pub fn call_stack(&self) -> &[usize] {
+        &self.stack[..self.sp.min(self.stack.len())]
+    }
+
+    /// Sets the stack pointer. Fails if it's past the end of the stack
+    pub fn set_sp(&mut self, sp: usize) -> Result<(), OutOfBounds> {
+        if sp > self.stack.len() {
+            return Err(OutOfBounds(sp));
+        }
+
+        self.sp = sp;
+        Ok(())
+    }
+
+    /// The keys held as of the last `tick`, e.g. to light up an on-screen keypad
+    pub fn keypad(&self) -> [bool; 16] {
+        self.keypad
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn set_delay_timer(&mut self, val: u8) {
+        self.delay_timer = val;
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    pub fn set_sound_timer(&mut self, val: u8) {
+        self.sound_timer = val;
+    }
+
+    /// Halts execution whenever pc lands on `addr`, before the opcode there is executed
+    pub fn add_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.remove(&addr);
+    }
+
+    /// Calls `f` with pc and the opcode before every opcode is executed. Useful to follow what a
+    /// misbehaving ROM is doing
+    pub fn set_trace(&mut self, f: Box<dyn FnMut(usize, u16)>) {
+        self.trace = Some(f);
+    }
+
+    /// How many times each opcode class was executed since power-on or the last reset, keyed
+    /// by `opcode_class`. Handy to check that a test ROM exercised every instruction
+    pub fn opcode_stats(&self) -> &HashMap<&'static str, u64> {
+        &self.opcode_histogram
+    }
+
+    /// Time spent executing each opcode class since power-on or the last reset, most expensive
+    /// first. Only built with the `profiling` feature so normal builds don't pay for the timing
+    #[cfg(feature = "profiling")]
+    pub fn profile_report(&self) -> alloc::vec::Vec<(&'static str, std::time::Duration)> {
+        let mut report: alloc::vec::Vec<_> = self.opcode_time.iter().map(|(&class, &time)| (class, time)).collect();
+        report.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        report
+    }
+
+    /// Sets what the vm does when it finds an opcode it doesn't decode
+    pub fn set_unknown_opcode_policy(&mut self, policy: UnknownOpcodePolicy) {
+        self.on_unknown_opcode = policy;
+    }
+
+    /// Sets the source of the random numbers CXKK draws from
+    pub fn set_rng(&mut self, rng: Box<dyn RngCore>) {
+        self.rng = rng;
+    }
+
+    /// Reports a possible infinite loop in `ProcessorState::stuck` once pc made no progress for
+    /// `ticks` ticks in a row, or never if `ticks` is `None`. Off by default
+    pub fn set_watchdog(&mut self, ticks: Option<usize>) {
+        self.watchdog = ticks;
+        self.stuck_ticks = 0;
+    }
+
+    /// Sets how many opcodes are executed in a single `tick`
+    pub fn set_speed(&mut self, cycles: usize) {
+        self.cycles_per_frame = cycles;
+    }
+
+    /// The RPL user flags, so a frontend can persist them between sessions
+    pub fn rpl_flags(&self) -> [u8; 8] {
+        self.rpl_flags
+    }
+
+    /// Restores previously persisted RPL user flags
+    pub fn set_rpl_flags(&mut self, flags: [u8; 8]) {
+        self.rpl_flags = flags;
+    }
+
+    /// Counts the delay and sound timers down by one. Must be called at 60Hz
+    pub fn tick_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
         }
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+    }
+
+    /// Loads the program at `PROGRAM_START`
+    pub fn load_program(&mut self, bytes: &[u8]) -> Result<(), LoadError> {
+        self.load_program_at(bytes, PROGRAM_START)
     }
 
-    pub fn load_program(&mut self, bytes: Vec<u8>) {
-        for i in 0..bytes.len() {
-            self.memory[i + 0x200] = bytes[i];
+    /// Swaps in a new program, e.g. when the frontend opens another ROM. Resets the vm like
+    /// `reset`, but also clears memory apart from the fonts and the rewind buffer, so nothing of
+    /// the old program is left, and then loads the new one at `PROGRAM_START`. The vm is left
+    /// as it was if the program doesn't fit
+    pub fn reload_program(&mut self, bytes: &[u8]) -> Result<(), LoadError> {
+        let available = self.memory.len().saturating_sub(PROGRAM_START);
+        if bytes.len() > available {
+            return Err(LoadError::TooLarge { len: bytes.len(), available });
+        }
+
+        self.reset();
+        self.memory.fill(0);
+        font::load_fonts(&mut self.memory);
+        if let Some(buffer) = self.rewind_buffer.as_mut() {
+            buffer.clear();
         }
+
+        self.load_program(bytes)
     }
 
-    fn get_opcode(&self) -> u16 {
-        (self.memory[self.pc] as u16) << 8 | (self.memory[self.pc + 1] as u16)
+    /// Loads the program at `load_at` and starts executing from there, e.g. at
+    /// `ETI660_PROGRAM_START` for ETI-660 programs. The vm restarts from `load_at` on reset too.
+    ///
+    /// An odd-length program is padded with a zero byte, so the fetch of its last opcode reads
+    /// `0x00` as the low byte rather than whatever an earlier program left there. A program that
+    /// ends at the top of memory isn't padded; the fetch wraps around to address 0 instead
+    pub fn load_program_at(&mut self, bytes: &[u8], load_at: usize) -> Result<(), LoadError> {
+        if load_at >= self.memory.len() {
+            return Err(LoadError::BadAddress(load_at));
+        }
+        let available = self.memory.len() - load_at;
+        if bytes.len() > available {
+            return Err(LoadError::TooLarge { len: bytes.len(), available });
+        }
+
+        self.memory[load_at..load_at + bytes.len()].copy_from_slice(bytes);
+        if bytes.len() % 2 == 1 {
+            if let Some(pad) = self.memory.get_mut(load_at + bytes.len()) {
+                *pad = 0;
+            }
+        }
+        self.load_address = load_at;
+        self.pc = load_at;
+        Ok(())
+    }
+
+    /// Executes one opcode.
+    ///
+    /// Returns false without executing anything if execution should halt
+    fn cycle(&mut self) -> bool {
+        if self.error.is_some() || self.halted {
+            return false;
+        }
+
+        let opcode = self.current_opcode();
+
+        // A waiting FX0A is executed again every cycle, but only halts at a breakpoint and
+        // shows up in the trace the first time
+        if !self.keypresswait {
+            if self.resume_from_breakpoint {
+                self.resume_from_breakpoint = false;
+            }
+            else if self.breakpoints.contains(&self.pc) {
+                self.hit_breakpoint = Some(self.pc);
+                self.resume_from_breakpoint = true;
+                return false;
+            }
+
+            if let Some(trace) = self.trace.as_mut() {
+                trace(self.pc, opcode);
+            }
+        }
+
+        let pc = self.pc;
+        let watched = self.watched_values();
+        self.execute_once(opcode);
+        self.check_watches(&watched, pc);
+
+        true
+    }
+
+    fn state(&self, beep: bool, cycles: usize) -> ProcessorState<'_> {
+        let (width, height) = self.resolution();
+
+        ProcessorState {
+            vram: &self.vram,
+            vram_changed: self.vram_changed,
+            beep,
+            cycles,
+            hires: self.hires,
+            resolution: (width as u16, height as u16),
+            collision: self.collision,
+            error: self.error,
+            halted: self.halted,
+            hit_breakpoint: self.hit_breakpoint,
+            hit_watch: self.hit_watch,
+            stuck: self.watchdog.is_some_and(|ticks| self.stuck_ticks >= ticks),
+            audio_pattern: self.audio_pattern,
+            audio_pitch: self.audio_pitch,
+            unknown_opcode: self.unknown_opcode
+        }
     }
 
     /// Executes one opcode and sets the program counter :)
@@ -112,9 +810,9 @@ impl Processor {
     /// I yanked some code from https://github.com/starrhorne/chip8-rust/blob/master/src/processor.rs as I'm noob
     fn execute_once(&mut self, opcode: u16) {
         let nibbles = (
-            (opcode & 0xF000) >> 12 as u8,
-            (opcode & 0x0F00) >> 8 as u8,
-            (opcode & 0x00F0) >> 4 as u8,
+            (opcode & 0xF000) >> 12,
+            (opcode & 0x0F00) >> 8,
+            (opcode & 0x00F0) >> 4,
             (opcode & 0x000F) as u8,
         );
 
@@ -125,9 +823,21 @@ impl Processor {
         let y = nibbles.2 as usize;
         let n = nibbles.3 as usize;
 
+        let class = opcode_class(opcode);
+        *self.opcode_histogram.entry(class).or_insert(0) += 1;
+
+        #[cfg(feature = "profiling")]
+        let started = std::time::Instant::now();
+
         match nibbles {
             (0x00, 0x00, 0x0e, 0x00) => self.op00e0(),
+            (0x00, 0x00, 0x0c, _) => self.op00cn(n),
             (0x00, 0x00, 0x0e, 0x0e) => self.op00ee(),
+            (0x00, 0x00, 0x0f, 0x0b) => self.op00fb(),
+            (0x00, 0x00, 0x0f, 0x0c) => self.op00fc(),
+            (0x00, 0x00, 0x0f, 0x0d) => self.op00fd(),
+            (0x00, 0x00, 0x0f, 0x0e) => self.op00fe(),
+            (0x00, 0x00, 0x0f, 0x0f) => self.op00ff(),
             (0x01, _, _, _) => self.op1nnn(nnn),
             (0x02, _, _, _) => self.op2nnn(nnn),
             (0x03, _, _, _) => self.op3xkk(x, kk),
@@ -141,56 +851,162 @@ impl Processor {
             (0x08, _, _, 0x03) => self.op8xy3(x, y),
             (0x08, _, _, 0x04) => self.op8xy4(x, y),
             (0x08, _, _, 0x05) => self.op8xy5(x, y),
-            (0x08, _, _, 0x06) => self.op8x06(x),
+            (0x08, _, _, 0x06) => self.op8xy6(x, y),
             (0x08, _, _, 0x07) => self.op8xy7(x, y),
-            (0x08, _, _, 0x0e) => self.op8x0e(x),
+            (0x08, _, _, 0x0e) => self.op8xye(x, y),
             (0x09, _, _, 0x00) => self.op9xy0(x, y),
             (0x0a, _, _, _) => self.opannn(nnn),
-            (0x0b, _, _, _) => self.opbnnn(nnn),
+            (0x0b, _, _, _) => self.opbnnn(x, nnn),
             (0x0c, _, _, _) => self.opcxkk(x, kk),
             (0x0d, _, _, _) => self.opdxyn(x, y, n),
             (0x0e, _, 0x09, 0x0e) => self.opex9e(x),
             (0x0e, _, 0x0a, 0x01) => self.opexa1(x),
+            (0x0f, 0x00, 0x00, 0x00) => self.opf000(),
+            (0x0f, 0x00, 0x00, 0x02) => self.opf002(),
+            (0x0f, _, 0x00, 0x01) => self.opfx01(x),
             (0x0f, _, 0x00, 0x07) => self.opfx07(x),
             (0x0f, _, 0x00, 0x0a) => self.opfx0a(x),
             (0x0f, _, 0x01, 0x05) => self.opfx15(x),
             (0x0f, _, 0x01, 0x08) => self.opfx18(x),
             (0x0f, _, 0x01, 0x0e) => self.opfx1e(x),
             (0x0f, _, 0x02, 0x09) => self.opfx29(x),
+            (0x0f, _, 0x03, 0x00) => self.opfx30(x),
             (0x0f, _, 0x03, 0x03) => self.opfx33(x),
+            (0x0f, _, 0x03, 0x0a) => self.opfx3a(x),
             (0x0f, _, 0x05, 0x05) => self.opfx55(x),
             (0x0f, _, 0x06, 0x05) => self.opfx65(x),
-            _ => self.pc_next()
+            (0x0f, _, 0x07, 0x05) => self.opfx75(x),
+            (0x0f, _, 0x08, 0x05) => self.opfx85(x),
+            _ => self.unknown(opcode)
+        }
+
+        #[cfg(feature = "profiling")]
+        {
+            *self.opcode_time.entry(class).or_default() += started.elapsed();
+        }
+    }
+
+    /// Handles an opcode the processor doesn't decode according to `on_unknown_opcode`
+    fn unknown(&mut self, opcode: u16) {
+        match self.on_unknown_opcode {
+            UnknownOpcodePolicy::Report => self.unknown_opcode = Some((self.pc, opcode)),
+            UnknownOpcodePolicy::Skip => {}
+            UnknownOpcodePolicy::Halt => {
+                self.unknown_opcode = Some((self.pc, opcode));
+                self.error = Some(EmuError::UnknownOpcode { pc: self.pc, opcode });
+                return;
+            }
+            UnknownOpcodePolicy::Callback(ref mut f) => {
+                self.unknown_opcode = Some((self.pc, opcode));
+                f(self.pc, opcode);
+            }
         }
+
+        self.pc_next();
     }
 
-    /// Clears the vram
+    /// Clears the selected XO-CHIP planes of the whole vram rather than just the active
+    /// resolution, so nothing drawn before a resolution switch survives. Pixels in planes that
+    /// aren't selected are kept, like Octo does. With every plane selected it's a plain fill
     fn op00e0(&mut self) {
-        for x in 0..32 {
-            for y in 0..64 {
-                self.vram[x][y] = 0;
+        if self.planes & 0b11 == 0b11 {
+            self.clear_vram();
+        }
+        else {
+            let mask = !self.planes;
+            for pixel in self.vram.iter_mut().flatten() {
+                *pixel &= mask;
             }
+            self.vram_changed = true;
         }
 
-        self.vram_changed = true;
         self.pc_next();
     }
 
     fn op00ee(&mut self) {
-        dbg!("op00ee");
+        if self.sp == 0 {
+            self.error = Some(EmuError::StackUnderflow);
+            return;
+        }
+
         self.sp -= 1;
         self.pc_jump(self.stack[self.sp]);
     }
 
+    /// Scrolls the display down by n lines, or n / 2 lines in low resolution mode
+    fn op00cn(&mut self, n: usize) {
+        let (width, height) = self.resolution();
+        let n = self.scroll_amount(n);
+
+        for y in (0..height).rev() {
+            for x in 0..width {
+                self.vram[y][x] = if y >= n { self.vram[y - n][x] } else { 0 };
+            }
+        }
+
+        self.vram_changed = true;
+        self.pc_next();
+    }
+
+    /// Scrolls the display right by 4 pixels, or 2 pixels in low resolution mode
+    fn op00fb(&mut self) {
+        let (width, height) = self.resolution();
+        let n = self.scroll_amount(4);
+
+        for y in 0..height {
+            for x in (0..width).rev() {
+                self.vram[y][x] = if x >= n { self.vram[y][x - n] } else { 0 };
+            }
+        }
+
+        self.vram_changed = true;
+        self.pc_next();
+    }
+
+    /// Scrolls the display left by 4 pixels, or 2 pixels in low resolution mode
+    fn op00fc(&mut self) {
+        let (width, height) = self.resolution();
+        let n = self.scroll_amount(4);
+
+        for y in 0..height {
+            for x in 0..width {
+                self.vram[y][x] = if x + n < width { self.vram[y][x + n] } else { 0 };
+            }
+        }
+
+        self.vram_changed = true;
+        self.pc_next();
+    }
+
+    /// Switches to the 64*32 low resolution mode
+    fn op00fe(&mut self) {
+        self.hires = false;
+        self.clear_vram();
+        self.pc_next();
+    }
+
+    /// Exits the interpreter. pc stays on the 00FD and nothing is executed anymore until reset
+    fn op00fd(&mut self) {
+        self.halted = true;
+    }
+
+    /// Switches to the SUPER-CHIP 128*64 high resolution mode
+    fn op00ff(&mut self) {
+        self.hires = true;
+        self.clear_vram();
+        self.pc_next();
+    }
+
     fn op1nnn(&mut self, nnn: usize) {
-        dbg!("op1nnn");
-        dbg!(nnn);
         self.pc_jump(nnn);
     }
 
     fn op2nnn(&mut self, nnn: usize) {
-        dbg!("op2nnn");
-        dbg!(nnn);
+        if self.sp >= self.stack.len() {
+            self.error = Some(EmuError::StackOverflow);
+            return;
+        }
+
         self.stack[self.sp] = self.pc + 2; // Next opcode
         self.sp += 1;
         self.pc_jump(nnn);
@@ -215,7 +1031,7 @@ impl Processor {
     }
 
     fn op5xy0(&mut self, x: usize, y: usize) {
-        if self.registers[x] != self.registers[y] {
+        if self.registers[x] == self.registers[y] {
             self.pc_skip();
         }
         else {
@@ -229,9 +1045,7 @@ impl Processor {
     }
 
     fn op7xkk(&mut self, x: usize, kk: u8) {
-        let (sum, ovrflw) = self.registers[x].overflowing_add(kk);
-        self.registers[x] = sum;
-        self.registers[0x0f] = ovrflw as u8;
+        self.registers[x] = self.registers[x].wrapping_add(kk);
         self.pc_next();
     }
 
@@ -255,6 +1069,10 @@ impl Processor {
         self.pc_next();
     }
 
+    /// Adds Vy to Vx and sets VF to the carry.
+    ///
+    /// Like 8XY5 and 8XY7, the result is written before the flag, so for 8FY4 VF ends up holding
+    /// the flag and the sum is discarded
     fn op8xy4(&mut self, x: usize, y: usize) {
         let vx = self.registers[x] as u16;
         let vy = self.registers[y] as u16;
@@ -265,27 +1083,43 @@ impl Processor {
         self.pc_next();
     }
 
+    /// Subtracts Vy from Vx and sets VF to 1 if there was no borrow. VF wins over the result
+    /// when x is F
     fn op8xy5(&mut self, x: usize, y: usize) {
-        self.registers[0x0f] = if self.registers[x] > self.registers[y] { 1 } else { 0 };
-        self.registers[x] = self.registers[x].wrapping_sub(self.registers[y]);
+        let (vx, vy) = (self.registers[x], self.registers[y]);
+
+        self.registers[x] = vx.wrapping_sub(vy);
+        self.registers[0x0f] = if vx >= vy { 1 } else { 0 };
         self.pc_next();
     }
 
-    fn op8x06(&mut self, x: usize) {
-        self.registers[0x0f] = self.registers[x] & 1;
-        self.registers[x] >>= 1;
+    /// Shifts Vx right by one, or Vy into Vx with the `shift_uses_vy` quirk, and sets VF to
+    /// the bit shifted out. VF wins over the result when x is F
+    fn op8xy6(&mut self, x: usize, y: usize) {
+        let source = if self.quirks.shift_uses_vy { self.registers[y] } else { self.registers[x] };
+
+        self.registers[x] = source >> 1;
+        self.registers[0x0f] = source & 1;
         self.pc_next();
     }
 
+    /// Sets Vx to Vy minus Vx and VF to 1 if there was no borrow. VF wins over the result
+    /// when x is F
     fn op8xy7(&mut self, x: usize, y: usize) {
-        self.registers[0x0f] = if self.registers[y] > self.registers[x] { 1 } else { 0 };
-        self.registers[x] = self.registers[y].wrapping_sub(self.registers[x]);
+        let (vx, vy) = (self.registers[x], self.registers[y]);
+
+        self.registers[x] = vy.wrapping_sub(vx);
+        self.registers[0x0f] = if vy >= vx { 1 } else { 0 };
         self.pc_next();
     }
 
-    fn op8x0e(&mut self, x: usize) {
-        self.registers[0x0f] = (self.registers[x] & 0b10000000) >> 7;
-        self.registers[x] <<= 1;
+    /// Shifts Vx left by one, or Vy into Vx with the `shift_uses_vy` quirk, and sets VF to
+    /// the bit shifted out. VF wins over the result when x is F
+    fn op8xye(&mut self, x: usize, y: usize) {
+        let source = if self.quirks.shift_uses_vy { self.registers[y] } else { self.registers[x] };
+
+        self.registers[x] = source << 1;
+        self.registers[0x0f] = (source & 0b10000000) >> 7;
         self.pc_next();
     }
 
@@ -303,15 +1137,13 @@ impl Processor {
         self.pc_next();
     }
 
-    fn opbnnn(&mut self, nnn: usize) {
-        dbg!("opbnnn");
-        dbg!(nnn);
-        self.pc_jump((self.registers[0] as usize) + nnn);
+    fn opbnnn(&mut self, x: usize, nnn: usize) {
+        let offset = if self.quirks.jump_uses_vx { self.registers[x] } else { self.registers[0] };
+        self.pc_jump((offset as usize) + nnn);
     }
 
     fn opcxkk(&mut self, x: usize, kk: u8) {
-        let mut rng = rand::thread_rng();
-        self.registers[x] = rand::Rng::gen::<u8>(&mut rng) & kk;
+        self.registers[x] = self.rng.gen::<u8>() & kk;
         self.pc_next();
     }
 
@@ -320,23 +1152,53 @@ impl Processor {
         // I don't know what I'm doing -_-
         // yanked directly from https://github.com/starrhorne/chip8-rust/blob/345602a97288fd8d69dafd6684e8f51cd38e95e2/src/processor.rs#L340
 
-        self.registers[0x0f] = 0;
-        for byte in 0..n {
-            let y = (self.registers[y] as usize + byte) % 32;
-            for bit in 0..8 {
-                let x = (self.registers[x] as usize + bit) % 64;
-                let color = (self.memory[self.i + byte] >> (7 - bit)) & 1;
-                self.registers[0x0f] |= color & self.vram[y][x];
-                self.vram[y][x] ^= color;
+        let (width, height) = self.resolution();
+
+        // DXY0 draws a 16*16 sprite made of two bytes per row in high resolution mode
+        let (rows, bytes_per_row) = if n == 0 && self.hires { (16, 2) } else { (n, 1) };
+
+        // With several planes selected, the sprite for each plane follows the one for the previous plane
+        let mut sprite_start = 0;
+        let planes = self.planes;
+
+        // The starting coordinate always wraps, only the pixels past the edge may be clipped
+        let start_x = self.registers[x] as usize % width;
+        let start_y = self.registers[y] as usize % height;
 
+        // VF is only written once the whole sprite is drawn
+        let mut collision = 0;
+        for plane in (0..2).filter(|plane| planes & (1 << plane) != 0) {
+            for row in 0..rows {
+                let y = start_y + row;
+                if y >= height && !self.quirks.sprite_wrap {
+                    break;
+                }
+                let y = y % height;
+                for bit in 0..bytes_per_row * 8 {
+                    let x = start_x + bit;
+                    if x >= width && !self.quirks.sprite_wrap {
+                        break;
+                    }
+                    let x = x % width;
+                    let byte = self.memory[self.i_offset(sprite_start + row * bytes_per_row + bit / 8)];
+                    let color = (byte >> (7 - bit % 8)) & 1;
+                    collision |= color & (self.vram[y][x] >> plane);
+                    self.vram[y][x] ^= color << plane;
+                }
             }
+            sprite_start += rows * bytes_per_row;
         }
+        self.registers[0x0f] = collision;
+        self.collision = collision == 1;
+        self.vblank_wait = self.quirks.display_wait;
         self.vram_changed = true;
         self.pc_next();
     }
     
+    /// Skips the next opcode if the key in Vx is held. Only the low nibble of Vx is used, like
+    /// most interpreters do
     fn opex9e(&mut self, x: usize) {
-        if self.keypad[self.registers[x] as usize] {
+        if self.keypad[(self.registers[x] & 0xf) as usize] {
             self.pc_skip();
         }
         else {
@@ -344,8 +1206,10 @@ impl Processor {
         }
     }
 
+    /// Skips the next opcode if the key in Vx is not held. Only the low nibble of Vx is used, like
+    /// most interpreters do
     fn opexa1(&mut self, x: usize) {
-        if !self.keypad[self.registers[x] as usize] {
+        if !self.keypad[(self.registers[x] & 0xf) as usize] {
             self.pc_skip();
         }
         else {
@@ -353,6 +1217,33 @@ impl Processor {
         }
     }
 
+    /// Selects the XO-CHIP planes that following draws and clears affect. x is the plane mask
+    fn opfx01(&mut self, x: usize) {
+        self.planes = (x & 0b11) as u8;
+        self.pc_next();
+    }
+
+    /// Points I at the 16 bit address in the two bytes after the opcode, so XO-CHIP programs
+    /// can reach all of memory
+    fn opf000(&mut self) {
+        let len = self.memory.len();
+        let hi = self.memory[(self.pc + 2) % len] as usize;
+        let lo = self.memory[(self.pc + 3) % len] as usize;
+
+        self.i = hi << 8 | lo;
+        self.pc += 4;
+    }
+
+    /// Loads the 16 byte XO-CHIP audio pattern from memory at I
+    fn opf002(&mut self) {
+        let mut pattern = [0; 16];
+        for (i, byte) in pattern.iter_mut().enumerate() {
+            *byte = self.memory[self.i_offset(i)];
+        }
+        self.audio_pattern = Some(pattern);
+        self.pc_next();
+    }
+
     fn opfx07(&mut self, x: usize) {
         self.registers[x] = self.delay_timer;
         self.pc_next();
@@ -361,7 +1252,17 @@ impl Processor {
     fn opfx0a(&mut self, x: usize) {
         self.keypresswait = true;
         self.key = x;
-        self.pc_next();
+
+        match self.pressed_key {
+            None => self.pressed_key = self.keypad.iter().position(|&pressed| pressed),
+            Some(key) if !self.keypad[key] => {
+                self.keypresswait = false;
+                self.pressed_key = None;
+                self.registers[x] = key as u8;
+                self.pc_next();
+            }
+            Some(_) => {}
+        }
     }
 
     fn opfx15(&mut self, x: usize) {
@@ -376,47 +1277,114 @@ impl Processor {
 
     fn opfx1e(&mut self, x: usize) {
         self.i += self.registers[x] as usize;
-        self.registers[0x0f] = if self.i > 0x0F00 { 1 } else { 0 };
+        if self.quirks.fx1e_sets_vf {
+            self.registers[0x0f] = if self.i > 0x0FFF { 1 } else { 0 };
+        }
         self.pc_next();
     }
 
     fn opfx29(&mut self, x: usize) {
-        self.i = (self.registers[x] as usize) * 5;
+        self.i = font::font_address(self.registers[x]);
+        self.pc_next();
+    }
+
+    /// Points I at the large SUPER-CHIP glyph for the digit in Vx
+    fn opfx30(&mut self, x: usize) {
+        self.i = font::big_font_address(self.registers[x]);
         self.pc_next();
     }
 
     fn opfx33(&mut self, x: usize) {
-        self.memory[self.i] = self.registers[x] / 100;
-        self.memory[self.i + 1] = (self.registers[x] % 100) / 10;
-        self.memory[self.i + 2] = self.registers[x] % 10;
+        let digits = [self.registers[x] / 100, (self.registers[x] % 100) / 10, self.registers[x] % 10];
+        for (i, &digit) in digits.iter().enumerate() {
+            let addr = self.i_offset(i);
+            self.memory[addr] = digit;
+        }
+        self.pc_next();
+    }
+
+    /// Sets the XO-CHIP audio pattern playback rate to Vx
+    fn opfx3a(&mut self, x: usize) {
+        self.audio_pitch = self.registers[x];
         self.pc_next();
     }
 
     fn opfx55(&mut self, x: usize) {
         for i in 0..x + 1 {
-            self.memory[self.i + i] = self.registers[i];
+            let addr = self.i_offset(i);
+            self.memory[addr] = self.registers[i];
+        }
+        if self.quirks.load_store_increments_i {
+            self.i += x + 1;
         }
         self.pc_next();
     }
 
     fn opfx65(&mut self, x: usize) {
         for i in 0..x + 1 {
-            self.registers[i] = self.memory[self.i + i];
+            self.registers[i] = self.memory[self.i_offset(i)];
+        }
+        if self.quirks.load_store_increments_i {
+            self.i += x + 1;
+        }
+        self.pc_next();
+    }
+
+    /// Width and height of the active display resolution
+    fn resolution(&self) -> (usize, usize) {
+        if self.hires {
+            (VRAM_WIDTH, VRAM_HEIGHT)
+        }
+        else {
+            (VRAM_WIDTH / 2, VRAM_HEIGHT / 2)
+        }
+    }
+
+    /// Scroll opcodes move by half the amount in low resolution mode as the pixels are twice as big
+    fn scroll_amount(&self, n: usize) -> usize {
+        if self.hires { n } else { n / 2 }
+    }
+
+    fn clear_vram(&mut self) {
+        self.vram = [[0; VRAM_WIDTH]; VRAM_HEIGHT];
+        self.vram_changed = true;
+    }
+
+    /// Stores V0 to Vx in the RPL user flags. Only 8 flags exist so x is capped at 7
+    fn opfx75(&mut self, x: usize) {
+        for i in 0..x.min(7) + 1 {
+            self.rpl_flags[i] = self.registers[i];
+        }
+        self.pc_next();
+    }
+
+    /// Loads V0 to Vx from the RPL user flags. Only 8 flags exist so x is capped at 7
+    fn opfx85(&mut self, x: usize) {
+        for i in 0..x.min(7) + 1 {
+            self.registers[i] = self.rpl_flags[i];
         }
         self.pc_next();
     }
 
+    /// Memory address `offset` bytes after I. Wraps around the end of memory like the hardware does
+    fn i_offset(&self, offset: usize) -> usize {
+        (self.i + offset) % self.memory.len()
+    }
+
     fn pc_next(&mut self) {
         self.pc += 2;
     }
 
     fn pc_jump(&mut self, addr: usize) {
-        dbg!(addr);
         self.pc = addr;
     }
 
+    /// Skips the next opcode. F000 NNNN is 4 bytes long, so it's skipped as a whole
     fn pc_skip(&mut self) {
-        dbg!(self.pc);
-        self.pc += 4;
+        let next = self.pc + 2;
+        let len = self.memory.len();
+        let long = self.memory[next % len] == 0xF0 && self.memory[(next + 1) % len] == 0x00;
+
+        self.pc += if long { 6 } else { 4 };
     }
 }
\ No newline at end of file
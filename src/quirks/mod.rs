@@ -0,0 +1,106 @@
+use serde::Deserialize;
+
+/// Interpreters whose quirks can be picked as a whole with `Quirks::for_platform`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Platform {
+    /// The original chip-8 interpreter on the COSMAC VIP
+    CosmacVip,
+
+    /// CHIP-48 on the HP-48 calculators
+    Chip48,
+
+    /// SUPER-CHIP 1.1
+    SuperChip,
+
+    /// XO-CHIP as implemented by Octo
+    XoChip
+}
+
+/// Names accepted by `Platform::from_name`, one per platform
+pub const PLATFORM_NAMES: [&str; 4] = ["vip", "chip48", "superchip", "xochip"];
+
+impl Platform {
+    /// Looks up a platform by one of `PLATFORM_NAMES`, ignoring case
+    pub fn from_name(name: &str) -> Option<Platform> {
+        let platforms = [Platform::CosmacVip, Platform::Chip48, Platform::SuperChip, Platform::XoChip];
+        PLATFORM_NAMES.iter()
+            .position(|platform| platform.eq_ignore_ascii_case(name))
+            .map(|index| platforms[index])
+    }
+}
+
+/// Behaviors that differ between chip-8 interpreters. ROMs written for one platform may
+/// misbehave on another, so these let the vm mimic the platform a ROM expects
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Quirks {
+    /// 8XY6 and 8XYE copy Vy into Vx before shifting, like the original COSMAC VIP
+    pub shift_uses_vy: bool,
+
+    /// FX55 and FX65 leave I pointing past the last register stored or loaded, i.e. at I + X + 1,
+    /// like the original COSMAC VIP. SUPER-CHIP leaves I unchanged
+    pub load_store_increments_i: bool,
+
+    /// BNNN is treated as BXNN and jumps to XNN plus Vx instead of NNN plus V0, like SUPER-CHIP
+    pub jump_uses_vx: bool,
+
+    /// FX1E sets VF when I overflows past 0xFFF, like the Amiga interpreter. Spacefight 2091!
+    /// depends on this
+    pub fx1e_sets_vf: bool,
+
+    /// Sprites that run off the right or bottom edge of the screen wrap around to the other
+    /// side instead of being clipped
+    pub sprite_wrap: bool,
+
+    /// DXYN waits for the vertical blank like on the COSMAC VIP, so it ends the frame and at most
+    /// one sprite is drawn per frame
+    pub display_wait: bool
+}
+
+/// The classic chip-8 behavior, apart from FX55 and FX65 which follow the COSMAC VIP and increment I
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            fx1e_sets_vf: false,
+            sprite_wrap: false,
+            display_wait: false
+        }
+    }
+}
+
+impl Quirks {
+    /// The quirks of `platform`
+    pub const fn for_platform(platform: Platform) -> Quirks {
+        match platform {
+            Platform::CosmacVip => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                fx1e_sets_vf: false,
+                sprite_wrap: false,
+                display_wait: true
+            },
+            // They only differ in ways the quirks don't cover, like CHIP-48 incrementing I by X
+            // instead of X + 1 in FX55 and FX65
+            Platform::Chip48 | Platform::SuperChip => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: false,
+                jump_uses_vx: true,
+                fx1e_sets_vf: false,
+                sprite_wrap: false,
+                display_wait: false
+            },
+            Platform::XoChip => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                fx1e_sets_vf: false,
+                sprite_wrap: true,
+                display_wait: false
+            }
+        }
+    }
+}
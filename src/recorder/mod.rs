@@ -0,0 +1,106 @@
+use std::fs::File;
+
+use sdl2::pixels;
+
+use crate::display::window_size;
+use crate::output::{Vram, VRAM_WIDTH, VRAM_HEIGHT};
+
+/// Only every this many emulator frames is recorded, as GIF frame delays are in hundredths
+/// of a second and can't match 60Hz
+const FRAME_STEP: usize = 3;
+
+/// GIF frame delay in hundredths of a second matching `FRAME_STEP`
+const FRAME_DELAY: u16 = 5;
+
+/// Most frames recorded unless told otherwise. 30 seconds of gameplay
+pub const DEFAULT_MAX_FRAMES: usize = 30 * 60 / FRAME_STEP;
+
+/// Records gameplay and saves it as an animated GIF
+pub struct GifRecorder {
+    frames: Vec<(Vram, bool)>,
+    recording: bool,
+    skipped: usize,
+    max_frames: usize,
+}
+
+impl GifRecorder {
+    /// Creates a recorder that keeps at most `max_frames` GIF frames. Frames past that are
+    /// dropped so memory use stays bounded
+    pub fn new(max_frames: usize) -> GifRecorder {
+        GifRecorder {
+            frames: Vec::new(),
+            recording: false,
+            skipped: 0,
+            max_frames,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Starts recording, discarding any frames of an unsaved recording
+    pub fn start(&mut self) {
+        self.frames.clear();
+        self.skipped = 0;
+        self.recording = true;
+    }
+
+    /// Records the vram of an emulator frame. Must be called once per frame while recording
+    pub fn record_frame(&mut self, vram: &Vram, hires: bool) {
+        if !self.recording || self.frames.len() >= self.max_frames {
+            return;
+        }
+
+        if self.skipped == 0 {
+            self.frames.push((*vram, hires));
+        }
+        self.skipped = (self.skipped + 1) % FRAME_STEP;
+    }
+
+    /// Stops recording and saves the recorded frames as an animated GIF at the given scale,
+    /// coloring pixel values 0 to 3 with the matching palette entry
+    pub fn stop(&mut self, path: &str, scale: u32, palette: [pixels::Color; 4]) -> Result<(), gif::EncodingError> {
+        self.recording = false;
+        let frames = std::mem::take(&mut self.frames);
+
+        let (width, height) = window_size(scale);
+        let global_palette: Vec<u8> = palette.iter().flat_map(|c| vec![c.r, c.g, c.b]).collect();
+
+        let mut encoder = gif::Encoder::new(File::create(path)?, width as u16, height as u16, &global_palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        for (vram, hires) in frames.iter() {
+            let frame = gif::Frame {
+                width: width as u16,
+                height: height as u16,
+                delay: FRAME_DELAY,
+                buffer: indexed_pixels(vram, *hires, width, height).into(),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The palette index of every pixel of a `width`*`height` image of the vram
+fn indexed_pixels(vram: &Vram, hires: bool, width: u32, height: u32) -> Vec<u8> {
+    let (vram_width, vram_height) = if hires {
+        (VRAM_WIDTH, VRAM_HEIGHT)
+    }
+    else {
+        (VRAM_WIDTH / 2, VRAM_HEIGHT / 2)
+    };
+
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let pixel = vram[y * vram_height / height as usize][x * vram_width / width as usize];
+            pixels.push(pixel & 0b11);
+        }
+    }
+
+    pixels
+}
@@ -0,0 +1,130 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+/// Writes the keypad state of every frame to a file so the session can be replayed.
+///
+/// The file starts with a `seed N` line holding the seed of the random number generator the
+/// session ran with, followed by `frame keys` lines where keys is a hex mask with bit k set if
+/// chip-8 key k is held. A line is only written when the keypad changes
+pub struct InputRecorder {
+    out: BufWriter<File>,
+    frame: u64,
+    last: Option<[bool; 16]>
+}
+
+impl InputRecorder {
+    /// Starts a recording of a session that runs with `Processor::with_seed(seed)`
+    pub fn record_to(path: &str, seed: u64) -> io::Result<InputRecorder> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "seed {}", seed)?;
+
+        Ok(InputRecorder {
+            out,
+            frame: 0,
+            last: None
+        })
+    }
+
+    /// Records the keypad passed to the next `tick`
+    pub fn record(&mut self, keypad: [bool; 16]) -> io::Result<()> {
+        if self.last != Some(keypad) {
+            writeln!(self.out, "{} {:04x}", self.frame, to_mask(keypad))?;
+            self.last = Some(keypad);
+        }
+        self.frame += 1;
+
+        Ok(())
+    }
+
+    /// Writes out anything still buffered
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Feeds a recorded session back frame by frame in place of live input
+pub struct InputPlayer {
+    seed: u64,
+    changes: Vec<(u64, [bool; 16])>,
+    next_change: usize,
+    frame: u64,
+    keypad: [bool; 16]
+}
+
+impl InputPlayer {
+    pub fn replay_from(path: &str) -> io::Result<InputPlayer> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+
+        let header = lines.next().unwrap_or_else(|| Ok(String::new()))?;
+        let seed = header
+            .strip_prefix("seed ")
+            .and_then(|seed| seed.parse().ok())
+            .ok_or_else(|| invalid_data("missing seed line"))?;
+
+        let mut changes = Vec::new();
+        for line in lines {
+            let line = line?;
+            let mut parts = line.split_whitespace();
+            let frame = parts.next().and_then(|frame| frame.parse().ok());
+            let mask = parts.next().and_then(|mask| u16::from_str_radix(mask, 16).ok());
+
+            match (frame, mask) {
+                (Some(frame), Some(mask)) => changes.push((frame, from_mask(mask))),
+                _ => return Err(invalid_data(&format!("bad line `{}`", line)))
+            }
+        }
+
+        Ok(InputPlayer {
+            seed,
+            changes,
+            next_change: 0,
+            frame: 0,
+            keypad: [false; 16]
+        })
+    }
+
+    /// The seed the recorded session ran with. Pass it to `Processor::with_seed` so random
+    /// numbers come out the same
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The keypad to pass to the next `tick`
+    pub fn next_frame(&mut self) -> [bool; 16] {
+        while let Some(&(frame, keypad)) = self.changes.get(self.next_change) {
+            if frame > self.frame {
+                break;
+            }
+            self.keypad = keypad;
+            self.next_change += 1;
+        }
+        self.frame += 1;
+
+        self.keypad
+    }
+
+    /// Set once every recorded keypad change has been played back
+    pub fn is_finished(&self) -> bool {
+        self.next_change >= self.changes.len()
+    }
+}
+
+fn to_mask(keypad: [bool; 16]) -> u16 {
+    keypad
+        .iter()
+        .enumerate()
+        .fold(0, |mask, (key, &pressed)| mask | ((pressed as u16) << key))
+}
+
+fn from_mask(mask: u16) -> [bool; 16] {
+    let mut keypad = [false; 16];
+    for (key, pressed) in keypad.iter_mut().enumerate() {
+        *pressed = mask & (1 << key) != 0;
+    }
+
+    keypad
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
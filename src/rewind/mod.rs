@@ -0,0 +1,84 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::processor::Processor;
+
+/// Frames kept by default, i.e. 5 seconds at 60Hz
+pub const DEFAULT_REWIND_FRAMES: usize = 5 * 60;
+
+/// Ring buffer of the most recent snapshots, one per frame. The oldest snapshot is dropped
+/// once the buffer is full, which bounds its memory use
+pub struct RewindBuffer {
+    states: VecDeque<Vec<u8>>,
+    capacity: usize
+}
+
+impl RewindBuffer {
+    pub fn new(capacity: usize) -> RewindBuffer {
+        RewindBuffer {
+            states: VecDeque::with_capacity(capacity),
+            capacity
+        }
+    }
+
+    /// How many frames can currently be rewound
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+
+    pub fn push(&mut self, state: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+        }
+        self.states.push_back(state);
+    }
+
+    /// Drops the newest `frames` snapshots and returns the oldest of them
+    fn pop(&mut self, frames: usize) -> Option<Vec<u8>> {
+        let frames = frames.min(self.states.len());
+        let at = self.states.len() - frames;
+
+        self.states.drain(at..).next()
+    }
+}
+
+impl Processor {
+    /// Starts capturing a snapshot at the start of every `tick` so execution can be rewound
+    /// by up to `capacity` frames
+    pub fn enable_rewind(&mut self, capacity: usize) {
+        self.rewind_buffer = Some(RewindBuffer::new(capacity));
+    }
+
+    pub fn disable_rewind(&mut self) {
+        self.rewind_buffer = None;
+    }
+
+    /// Restores the state the vm was in `frames` ticks ago, or as far back as the buffer goes.
+    /// Returns how many frames were actually rewound
+    pub fn rewind(&mut self, frames: usize) -> usize {
+        let buffer = match self.rewind_buffer.as_mut() {
+            Some(buffer) => buffer,
+            None => return 0
+        };
+        let frames = frames.min(buffer.len());
+
+        if let Some(state) = buffer.pop(frames) {
+            // Snapshots are made by this processor, so they always restore
+            self.restore(&state).expect("snapshots taken by the rewind buffer always restore");
+            self.vram_changed = true;
+        }
+
+        frames
+    }
+}
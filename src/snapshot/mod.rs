@@ -0,0 +1,194 @@
+use alloc::vec::Vec;
+
+use crate::output::{VRAM_WIDTH, VRAM_HEIGHT};
+use crate::processor::Processor;
+
+/// Marks the start of every snapshot
+const MAGIC: &[u8; 4] = b"C8SS";
+
+/// Bumped whenever the snapshot layout changes
+const VERSION: u8 = 4;
+
+/// Errors that can happen while restoring a snapshot
+#[derive(Debug, PartialEq)]
+pub enum SnapshotError {
+    /// The bytes don't start with the snapshot magic
+    BadMagic,
+
+    /// The snapshot was made by an incompatible version. Holds the version of the snapshot
+    UnsupportedVersion(u8),
+
+    /// The snapshot is shorter or longer than its layout says it should be
+    BadLength,
+
+    /// The snapshot was made with a differently sized memory or stack
+    SizeMismatch,
+
+    /// The snapshot holds a state the vm can't be in, like a stack pointer past the stack or
+    /// a key that isn't on the keypad
+    BadState
+}
+
+impl core::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a snapshot"),
+            SnapshotError::UnsupportedVersion(v) => write!(f, "unsupported snapshot version {}", v),
+            SnapshotError::BadLength => write!(f, "snapshot has the wrong length"),
+            SnapshotError::SizeMismatch => write!(f, "snapshot was made with a different memory or stack size"),
+            SnapshotError::BadState => write!(f, "snapshot holds an impossible state")
+        }
+    }
+}
+
+/// Reads the snapshot bytes in order, failing if they run out
+struct Reader<'a> {
+    bytes: &'a [u8]
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotError::BadLength);
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+fn push_u32(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+impl Processor {
+    /// Captures the state of the vm in a compact binary encoding. Quirks, speed, the RPL
+    /// user flags and the random number generator aren't part of it, so CXKK draws different
+    /// numbers after a restore than it did the first time
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+
+        push_u32(&mut out, self.memory.len());
+        out.extend_from_slice(&self.memory);
+        out.extend_from_slice(&self.registers);
+        push_u32(&mut out, self.stack.len());
+        for &addr in self.stack.iter() {
+            push_u32(&mut out, addr);
+        }
+        push_u32(&mut out, self.sp);
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        out.push(self.audio_pattern.is_some() as u8);
+        out.extend_from_slice(&self.audio_pattern.unwrap_or([0; 16]));
+        out.push(self.audio_pitch);
+        for row in self.vram.iter() {
+            out.extend_from_slice(row);
+        }
+        out.push(self.hires as u8);
+        out.push(self.planes);
+        push_u32(&mut out, self.pc);
+        push_u32(&mut out, self.i);
+        out.extend(self.keypad.iter().map(|&k| k as u8));
+        out.push(self.keypresswait as u8);
+        out.push(self.key as u8);
+        out.push(self.pressed_key.map_or(0xff, |k| k as u8));
+        out.push(self.halted as u8);
+
+        out
+    }
+
+    /// Restores a state captured by `snapshot`.
+    ///
+    /// The magic, version, length and memory and stack sizes of the snapshot are validated
+    /// before anything is touched, and so are the stack pointer, the waited for keys and the
+    /// selected planes, which later opcodes index with. The vm is left as it was if an error
+    /// is returned. Otherwise any error that stopped the vm is cleared
+    pub fn restore(&mut self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        let mut r = Reader { bytes };
+
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = r.u8()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        if r.u32()? as usize != self.memory.len() {
+            return Err(SnapshotError::SizeMismatch);
+        }
+        let memory = r.take(self.memory.len())?;
+        let registers = r.take(self.registers.len())?;
+        if r.u32()? as usize != self.stack.len() {
+            return Err(SnapshotError::SizeMismatch);
+        }
+        let mut stack = self.stack;
+        for addr in stack.iter_mut() {
+            *addr = r.u32()? as usize;
+        }
+        let sp = r.u32()? as usize;
+        let delay_timer = r.u8()?;
+        let sound_timer = r.u8()?;
+        let has_audio_pattern = r.u8()? != 0;
+        let mut audio_pattern = [0; 16];
+        audio_pattern.copy_from_slice(r.take(16)?);
+        let audio_pitch = r.u8()?;
+        let vram = r.take(VRAM_WIDTH * VRAM_HEIGHT)?;
+        let hires = r.u8()? != 0;
+        let planes = r.u8()?;
+        let pc = r.u32()? as usize;
+        let i = r.u32()? as usize;
+        let keypad = r.take(self.keypad.len())?;
+        let keypresswait = r.u8()? != 0;
+        let key = r.u8()? as usize;
+        let pressed_key = r.u8()?;
+        let halted = r.u8()? != 0;
+
+        if !r.bytes.is_empty() {
+            return Err(SnapshotError::BadLength);
+        }
+        let keys = self.keypad.len();
+        if sp > stack.len() || planes > 3 || key >= keys || (pressed_key as usize >= keys && pressed_key != 0xff) {
+            return Err(SnapshotError::BadState);
+        }
+
+        self.memory.copy_from_slice(memory);
+        self.registers.copy_from_slice(registers);
+        self.stack = stack;
+        self.sp = sp;
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.audio_pattern = if has_audio_pattern { Some(audio_pattern) } else { None };
+        self.audio_pitch = audio_pitch;
+        for (row, bytes) in self.vram.iter_mut().zip(vram.chunks(VRAM_WIDTH)) {
+            row.copy_from_slice(bytes);
+        }
+        self.hires = hires;
+        self.planes = planes;
+        self.pc = pc;
+        self.i = i;
+        for (k, &b) in self.keypad.iter_mut().zip(keypad) {
+            *k = b != 0;
+        }
+        self.keypresswait = keypresswait;
+        self.key = key;
+        self.pressed_key = if pressed_key == 0xff { None } else { Some(pressed_key as usize) };
+        self.halted = halted;
+        self.error = None;
+
+        Ok(())
+    }
+}
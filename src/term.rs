@@ -0,0 +1,81 @@
+//! Runs a ROM in the terminal instead of an SDL window. There's no sound. Press Esc to quit
+use chipvm::{cartridge, processor, quirks, terminal, timing};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let cartridge_filename = match args.get(1) {
+        Some(filename) if !filename.starts_with("--") => filename,
+        _ => {
+            eprintln!("Usage: {} ROM [--platform NAME]", args[0]);
+            std::process::exit(2);
+        }
+    };
+
+    let cartridge_driver = match cartridge::Cartridge::open(cartridge_filename) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Couldn't read ROM {}: {}", cartridge_filename, e);
+            std::process::exit(1);
+        }
+    };
+    let mut processor = processor::Processor::new();
+    if cartridge_driver.rom.len() > processor::MEMORY_SIZE - processor::PROGRAM_START {
+        processor.memory.resize(processor::XO_CHIP_MEMORY_SIZE, 0);
+    }
+    if let Some(info) = cartridge::detect(&cartridge_driver.rom) {
+        processor.quirks = info.quirks;
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--platform") {
+        match args.get(i + 1).and_then(|name| quirks::Platform::from_name(name)) {
+            Some(platform) => processor.quirks = quirks::Quirks::for_platform(platform),
+            None => {
+                eprintln!("Expected --platform to be one of {}", quirks::PLATFORM_NAMES.join(", "));
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Err(e) = processor.load_program(&cartridge_driver.rom) {
+        eprintln!("Couldn't load ROM {}: {}", cartridge_filename, e);
+        std::process::exit(1);
+    }
+
+    let mut display = match terminal::TermDisplay::new() {
+        Ok(display) => display,
+        Err(e) => {
+            eprintln!("Couldn't set up the terminal: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut input = terminal::TermInput::new();
+    let mut frame_timer = timing::FrameTimer::new(timing::SystemClock::new());
+    let mut throttle = timing::IpsThrottle::new(timing::DEFAULT_TARGET_IPS);
+
+    let error = 'emulation: loop {
+        for _ in 0..frame_timer.frames_due() {
+            let keypad = match input.poll() {
+                Ok(Some(keypad)) => keypad,
+                _ => break 'emulation None
+            };
+
+            processor.set_speed(throttle.cycles_for_frame());
+            let output = processor.tick(keypad);
+            if let Some(e) = output.error {
+                break 'emulation Some(e);
+            }
+            if output.halted {
+                break 'emulation None;
+            }
+            if output.vram_changed {
+                display.draw(output.vram, output.hires);
+            }
+        }
+
+        std::thread::sleep(frame_timer.until_next_frame());
+    };
+
+    // Restore the terminal before printing anything
+    drop(display);
+    if let Some(e) = error {
+        eprintln!("Emulation stopped at {:#05x}: {}", processor.pc, e);
+    }
+}
@@ -0,0 +1,123 @@
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, queue, style, terminal};
+
+use crate::output::{Vram, VRAM_WIDTH, VRAM_HEIGHT};
+
+const CHIP8_HEIGHT: usize = 32;
+const CHIP8_WIDTH: usize = 64;
+
+/// Terminal keys for the chip-8 keys 0x0 to 0xF, laid out like the classic keyboard grid
+const TERM_KEYS: [(char, u8); 16] = [
+    ('1', 0x1), ('2', 0x2), ('3', 0x3), ('4', 0xC),
+    ('q', 0x4), ('w', 0x5), ('e', 0x6), ('r', 0xD),
+    ('a', 0x7), ('s', 0x8), ('d', 0x9), ('f', 0xE),
+    ('z', 0xA), ('x', 0x0), ('c', 0xB), ('v', 0xF),
+];
+
+/// Frames a key stays held after it's pressed. Terminals don't report key releases, so a key
+/// counts as held as long as the terminal keeps repeating it
+const HOLD_FRAMES: u8 = 6;
+
+/// Draws the vram in the terminal, two pixels per character cell using half block characters.
+/// Meant for running over SSH or anywhere else SDL isn't available
+pub struct TermDisplay {
+    out: Stdout
+}
+
+impl TermDisplay {
+    /// Switches the terminal to the alternate screen. The terminal is restored on drop
+    pub fn new() -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        queue!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+        out.flush()?;
+
+        Ok(TermDisplay { out })
+    }
+
+    /// Draws the vram. Only the top left 64*32 pixels are drawn unless `hires` is set
+    pub fn draw(&mut self, pixels: &Vram, hires: bool) {
+        let _ = self.try_draw(pixels, hires);
+    }
+
+    fn try_draw(&mut self, pixels: &Vram, hires: bool) -> io::Result<()> {
+        let (width, height) = if hires {
+            (VRAM_WIDTH, VRAM_HEIGHT)
+        }
+        else {
+            (CHIP8_WIDTH, CHIP8_HEIGHT)
+        };
+
+        for (line, rows) in pixels[..height].chunks(2).enumerate() {
+            let text: String = (0..width)
+                .map(|x| match (rows[0][x] != 0, rows[1][x] != 0) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' '
+                })
+                .collect();
+            queue!(self.out, cursor::MoveTo(0, line as u16), style::Print(text))?;
+        }
+
+        self.out.flush()
+    }
+}
+
+impl Drop for TermDisplay {
+    fn drop(&mut self) {
+        let _ = queue!(self.out, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = self.out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Reads the chip-8 keypad from terminal key presses
+#[derive(Default)]
+pub struct TermInput {
+    /// Frames left until each chip-8 key is released
+    held: [u8; 16]
+}
+
+impl TermInput {
+    pub fn new() -> Self {
+        TermInput::default()
+    }
+
+    /// Returns the keypad state for the next frame, or `None` if Esc or Ctrl+C was pressed
+    pub fn poll(&mut self) -> io::Result<Option<[bool; 16]>> {
+        for held in self.held.iter_mut() {
+            *held = held.saturating_sub(1);
+        }
+
+        while event::poll(Duration::from_secs(0))? {
+            if let Event::Key(KeyEvent { code, modifiers, kind, .. }) = event::read()? {
+                if kind == KeyEventKind::Release {
+                    continue;
+                }
+
+                match code {
+                    KeyCode::Esc => return Ok(None),
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+                    KeyCode::Char(c) => {
+                        let c = c.to_ascii_lowercase();
+                        if let Some(&(_, key)) = TERM_KEYS.iter().find(|(term_key, _)| *term_key == c) {
+                            self.held[key as usize] = HOLD_FRAMES;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut keypad = [false; 16];
+        for (pressed, &held) in keypad.iter_mut().zip(self.held.iter()) {
+            *pressed = held > 0;
+        }
+
+        Ok(Some(keypad))
+    }
+}
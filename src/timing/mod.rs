@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+/// How long a single 60Hz frame lasts
+pub const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Opcodes executed per second unless set otherwise. Matches 10 opcodes per frame
+pub const DEFAULT_TARGET_IPS: u32 = 600;
+
+/// How many times faster the emulation runs in turbo mode unless set otherwise
+pub const DEFAULT_TURBO_FACTOR: usize = 8;
+
+/// Most frames `FrameTimer::frames_due` reports at once. Anything beyond that is dropped so
+/// a long stall doesn't make the game run in fast forward to catch up
+const MAX_FRAMES_DUE: u32 = 4;
+
+/// A source of the current time, so frame timing can be driven by a fake clock
+pub trait Clock {
+    /// Time passed since some fixed point
+    fn now(&self) -> Duration;
+}
+
+/// The real clock
+pub struct SystemClock {
+    start: Instant
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Keeps frames running at 60Hz. Time that passed but didn't add up to a whole frame is
+/// carried over to the next call, so slow or fast loop iterations don't make it drift
+pub struct FrameTimer<C: Clock> {
+    clock: C,
+    last: Duration,
+    accumulator: Duration
+}
+
+impl<C: Clock> FrameTimer<C> {
+    pub fn new(clock: C) -> FrameTimer<C> {
+        let last = clock.now();
+
+        FrameTimer {
+            clock,
+            last,
+            accumulator: Duration::from_secs(0)
+        }
+    }
+
+    /// How many frames should be run since the last call
+    pub fn frames_due(&mut self) -> u32 {
+        let now = self.clock.now();
+        self.accumulator += now - self.last;
+        self.last = now;
+
+        let mut frames = 0;
+        while self.accumulator >= FRAME_DURATION {
+            self.accumulator -= FRAME_DURATION;
+            frames += 1;
+        }
+
+        frames.min(MAX_FRAMES_DUE)
+    }
+
+    /// How long until the next frame is due, as of the last `frames_due` call
+    pub fn until_next_frame(&self) -> Duration {
+        FRAME_DURATION - self.accumulator
+    }
+}
+
+/// Spreads a target number of opcodes per second over 60Hz frames. Targets that aren't a
+/// multiple of 60 carry the leftover opcodes over to later frames, so every second runs exactly
+/// `target_ips` opcodes no matter how long rendering takes
+pub struct IpsThrottle {
+    target_ips: u32,
+    carried: u32
+}
+
+impl IpsThrottle {
+    pub fn new(target_ips: u32) -> IpsThrottle {
+        IpsThrottle {
+            target_ips,
+            carried: 0
+        }
+    }
+
+    pub fn target_ips(&self) -> u32 {
+        self.target_ips
+    }
+
+    pub fn set_target_ips(&mut self, target_ips: u32) {
+        self.target_ips = target_ips;
+        self.carried = 0;
+    }
+
+    /// How many opcodes the next frame should execute
+    pub fn cycles_for_frame(&mut self) -> usize {
+        let total = self.target_ips + self.carried;
+        self.carried = total % 60;
+
+        (total / 60) as usize
+    }
+}
+
+/// Frames and opcodes per second as measured by a `RateCounter`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rates {
+    pub fps: u32,
+    pub ips: u64
+}
+
+/// Measures how many frames and opcodes actually run per second. The counts are collected
+/// over about a second and then turned into rates, so they're updated once per second
+pub struct RateCounter<C: Clock> {
+    clock: C,
+    since: Duration,
+    frames: u32,
+    opcodes: u64,
+    rates: Rates
+}
+
+impl<C: Clock> RateCounter<C> {
+    pub fn new(clock: C) -> RateCounter<C> {
+        let since = clock.now();
+
+        RateCounter {
+            clock,
+            since,
+            frames: 0,
+            opcodes: 0,
+            rates: Rates::default()
+        }
+    }
+
+    /// Counts a frame that executed `opcodes` opcodes. Returns the new rates if a second or
+    /// more passed since they were last updated
+    pub fn frame(&mut self, opcodes: usize) -> Option<Rates> {
+        self.frames += 1;
+        self.opcodes += opcodes as u64;
+
+        let elapsed = self.clock.now() - self.since;
+        if elapsed < Duration::from_secs(1) {
+            return None;
+        }
+
+        let per_second = |count: u64| (count as u128 * 1_000_000_000 / elapsed.as_nanos()) as u64;
+        self.rates = Rates {
+            fps: per_second(self.frames as u64) as u32,
+            ips: per_second(self.opcodes)
+        };
+        self.since += elapsed;
+        self.frames = 0;
+        self.opcodes = 0;
+
+        Some(self.rates)
+    }
+
+    /// The rates as of the last update
+    pub fn rates(&self) -> Rates {
+        self.rates
+    }
+}
@@ -0,0 +1,71 @@
+use alloc::vec::Vec;
+
+use crate::processor::Processor;
+
+/// A value execution halts on when it changes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Watch {
+    /// Vx
+    Register(usize),
+
+    /// The byte at an address
+    Memory(usize)
+}
+
+/// A watched value that changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchHit {
+    pub watch: Watch,
+
+    /// Address of the opcode that changed the value
+    pub pc: usize,
+
+    pub old: u8,
+    pub new: u8
+}
+
+impl Processor {
+    /// Halts execution right after an opcode changes Vx
+    pub fn add_register_watch(&mut self, x: usize) {
+        self.add_watch(Watch::Register(x));
+    }
+
+    /// Halts execution right after an opcode changes the byte at `addr`
+    pub fn add_memory_watch(&mut self, addr: usize) {
+        self.add_watch(Watch::Memory(addr));
+    }
+
+    pub fn remove_watch(&mut self, watch: Watch) {
+        self.watches.retain(|&w| w != watch);
+    }
+
+    fn add_watch(&mut self, watch: Watch) {
+        if !self.watches.contains(&watch) {
+            self.watches.push(watch);
+        }
+    }
+
+    /// The current value of every watch, in the order the watches were added. Values that
+    /// are out of range read as 0
+    pub(crate) fn watched_values(&self) -> Vec<u8> {
+        self.watches
+            .iter()
+            .map(|&watch| match watch {
+                Watch::Register(x) => self.registers.get(x).copied().unwrap_or(0),
+                Watch::Memory(addr) => self.read_memory(addr).unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// Records the first watch whose value differs from `before`, which the opcode at `pc`
+    /// changed
+    pub(crate) fn check_watches(&mut self, before: &[u8], pc: usize) {
+        let after = self.watched_values();
+
+        self.hit_watch = self.watches
+            .iter()
+            .zip(before.iter().zip(after.iter()))
+            .find(|(_, (old, new))| old != new)
+            .map(|(&watch, (&old, &new))| WatchHit { watch, pc, old, new });
+    }
+}
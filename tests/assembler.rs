@@ -0,0 +1,66 @@
+use chipvm::assembler::{assemble, AsmError};
+use chipvm::disasm::disassemble_rom;
+use chipvm::headless::run_headless;
+
+#[test]
+fn assembled_program_runs() {
+    let rom = assemble("
+        ; Draws the digit 7 in the top left corner
+                LD V0, 7
+                CALL draw
+        done:   JP done
+
+        draw:   LD F, V0
+                LD V1, 0
+                DRW V1, V1, 5
+                RET
+    ").unwrap();
+
+    let screen = run_headless(&rom, 1).unwrap().pop().unwrap();
+    let rows: Vec<Vec<u8>> = screen.iter().take(5).map(|row| row[..4].to_vec()).collect();
+
+    assert_eq!(rows, vec![
+        vec![1, 1, 1, 1],
+        vec![0, 0, 0, 1],
+        vec![0, 0, 1, 0],
+        vec![0, 1, 0, 0],
+        vec![0, 1, 0, 0],
+    ]);
+}
+
+#[test]
+fn disassembled_roms_assemble_to_the_same_bytes() {
+    let games = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("games");
+
+    for entry in std::fs::read_dir(games).unwrap() {
+        let rom = std::fs::read(entry.unwrap().path()).unwrap();
+        let source: Vec<String> = disassemble_rom(&rom).into_iter().map(|(_, text)| text).collect();
+
+        assert_eq!(assemble(&source.join("\n")).unwrap(), rom);
+    }
+}
+
+#[test]
+fn long_loads_take_four_bytes() {
+    let source = "
+                LD I, long data
+        done:   JP done
+        data:   DB 0x42
+    ";
+    let rom = assemble(source).unwrap();
+
+    assert_eq!(rom, [0xF0, 0x00, 0x02, 0x06, 0x12, 0x04, 0x42]);
+    let source: Vec<String> = disassemble_rom(&rom).into_iter().map(|(_, text)| text).collect();
+    assert_eq!(assemble(&source.join("\n")).unwrap(), rom);
+}
+
+#[test]
+fn errors_name_the_line() {
+    assert_eq!(
+        assemble("CLS\nJMP 0x200"),
+        Err(AsmError::UnknownMnemonic { line: 2, mnemonic: "JMP".to_string() })
+    );
+    assert_eq!(assemble("LD V0, 256"), Err(AsmError::OutOfRange { line: 1, value: 256 }));
+    assert_eq!(assemble("JP nowhere"), Err(AsmError::UnknownLabel { line: 1, label: "nowhere".to_string() }));
+    assert_eq!(assemble("DRW V0, V1"), Err(AsmError::BadOperands { line: 1 }));
+}
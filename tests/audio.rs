@@ -0,0 +1,36 @@
+#![cfg(feature = "sdl")]
+
+use chipvm::audio::{Oscillator, Waveform, DEFAULT_FREQUENCY, DEFAULT_VOLUME};
+use sdl2::audio::AudioCallback;
+
+/// Sample rate that fits exactly 100 samples in a period of the default pitch
+const SAMPLE_RATE: i32 = DEFAULT_FREQUENCY as i32 * 100;
+
+#[test]
+fn samples_follow_the_waveform_at_the_volume() {
+    for &waveform in [Waveform::Square, Waveform::Sine, Waveform::Triangle, Waveform::Sawtooth].iter() {
+        let mut oscillator = Oscillator::new(waveform, SAMPLE_RATE);
+        let mut samples = [0.0; 200];
+        oscillator.callback(&mut samples);
+
+        // The phase is accumulated in steps of 1/100, the way the oscillator does it, so the
+        // square wave flips on the same sample despite rounding
+        let mut phase = 0.0f32;
+        for (i, &sample) in samples.iter().enumerate() {
+            let expected = DEFAULT_VOLUME * waveform.sample(phase);
+            assert!((sample - expected).abs() < 1e-6, "{:?} sample {}: {} != {}", waveform, i, sample, expected);
+            phase = (phase + DEFAULT_FREQUENCY / SAMPLE_RATE as f32) % 1.0;
+        }
+    }
+}
+
+#[test]
+fn waveforms_have_the_expected_shape() {
+    assert_eq!(Waveform::Square.sample(0.25), 1.0);
+    assert_eq!(Waveform::Square.sample(0.75), -1.0);
+    assert!((Waveform::Sine.sample(0.25) - 1.0).abs() < 1e-6);
+    assert_eq!(Waveform::Triangle.sample(0.0), -1.0);
+    assert_eq!(Waveform::Triangle.sample(0.5), 1.0);
+    assert_eq!(Waveform::Sawtooth.sample(0.0), -1.0);
+    assert_eq!(Waveform::Sawtooth.sample(0.75), 0.5);
+}
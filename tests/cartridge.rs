@@ -0,0 +1,49 @@
+use std::io::{Cursor, Write};
+
+use chipvm::cartridge::{self, RomInfo};
+use chipvm::quirks::{Platform, Quirks};
+use chipvm::Cartridge;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+const ROM: [u8; 4] = [0x60, 0x07, 0x12, 0x02];
+
+#[test]
+fn reads_rom_from_a_reader() {
+    let cartridge = Cartridge::from_reader(Cursor::new(ROM.to_vec())).unwrap();
+
+    assert_eq!(cartridge.rom, ROM);
+    assert_eq!(cartridge.bytes_read, ROM.len());
+}
+
+#[test]
+fn missing_file_is_an_error() {
+    let error = Cartridge::read("does/not/exist.ch8").err().unwrap();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn reads_gzipped_rom_from_a_reader() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&ROM).unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    let cartridge = Cartridge::from_reader(Cursor::new(gzipped.clone())).unwrap();
+
+    assert_eq!(cartridge.rom, ROM);
+    assert_eq!(cartridge.bytes_read, gzipped.len());
+}
+
+#[test]
+fn known_roms_are_detected_with_their_quirks() {
+    let chip48 = Quirks::for_platform(Platform::Chip48);
+
+    let pong = std::fs::read("games/PONG").unwrap();
+    assert_eq!(cartridge::detect(&pong), Some(RomInfo { name: "Pong", quirks: chip48 }));
+
+    let brix = std::fs::read("games/BRIX").unwrap();
+    assert_eq!(cartridge::detect(&brix).map(|info| info.name), Some("Brix"));
+
+    assert_eq!(cartridge::detect(&ROM), None);
+}
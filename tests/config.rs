@@ -0,0 +1,50 @@
+use chipvm::config::Config;
+use chipvm::quirks::Quirks;
+
+const SAMPLE: &str = "
+scale = 12
+target_ips = 700
+
+[quirks]
+shift_uses_vy = false
+sprite_wrap = true
+
+[keys]
+Up = 2
+Left = 4
+";
+
+#[test]
+fn sample_config_sets_its_quirks_and_keys() {
+    let config = Config::parse(SAMPLE).unwrap();
+
+    assert_eq!((config.scale, config.target_ips), (12, 700));
+    assert_eq!(config.quirks, Some(Quirks { shift_uses_vy: false, sprite_wrap: true, ..Quirks::default() }));
+    assert_eq!(config.keys.get("Up"), Some(&2));
+    assert_eq!(config.keys.get("Left"), Some(&4));
+    assert_eq!(config.keys.len(), 2);
+}
+
+#[test]
+fn quirks_are_only_set_by_a_quirks_table() {
+    // Without one the quirks picked for known games are kept
+    assert_eq!(Config::parse("scale = 12").unwrap().quirks, None);
+    assert_eq!(Config::parse("[quirks]").unwrap().quirks, Some(Quirks::default()));
+}
+
+#[test]
+fn unknown_settings_are_rejected() {
+    assert!(Config::parse("[quirks]\nshift_uses_vx = true").is_err());
+    assert!(Config::parse("scael = 12").is_err());
+}
+
+#[cfg(feature = "sdl")]
+#[test]
+fn sample_config_keys_make_a_keymap() {
+    use chipvm::input::KeyMap;
+    use sdl2::keyboard::Keycode;
+
+    let config = Config::parse(SAMPLE).unwrap();
+
+    assert_eq!(KeyMap::from_names(&config.keys), Ok(KeyMap::from_pairs(&[(Keycode::Up, 2), (Keycode::Left, 4)])));
+}
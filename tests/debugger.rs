@@ -0,0 +1,144 @@
+use chipvm::Processor;
+
+/// Calls a subroutine which calls another one before returning
+const PROGRAM: [u8; 14] = [
+    0x22, 0x06, // 200: CALL 206
+    0x61, 0x02, // 202: LD V1, 2
+    0x12, 0x04, // 204: JP 204
+    0x22, 0x0a, // 206: CALL 20A
+    0x00, 0xee, // 208: RET
+    0x60, 0x01, // 20A: LD V0, 1
+    0x00, 0xee, // 20C: RET
+];
+
+#[test]
+fn step_over_runs_the_whole_call() {
+    let mut processor = Processor::new();
+    processor.load_program(&PROGRAM).unwrap();
+
+    processor.step_over();
+    assert_eq!(processor.pc(), 0x202);
+    assert_eq!(processor.sp(), 0);
+    assert_eq!(processor.register(0), 1);
+    assert_eq!(processor.register(1), 0);
+
+    processor.step_over();
+    assert_eq!(processor.pc(), 0x204);
+    assert_eq!(processor.register(1), 2);
+}
+
+#[test]
+fn step_over_stops_at_breakpoints_inside_the_call() {
+    let mut processor = Processor::new();
+    processor.load_program(&PROGRAM).unwrap();
+    processor.add_breakpoint(0x20a);
+
+    let state = processor.step_over();
+    assert_eq!(state.hit_breakpoint, Some(0x20a));
+    assert_eq!(processor.pc(), 0x20a);
+}
+
+#[test]
+fn step_over_stops_when_the_call_waits_for_a_key() {
+    let rom = chipvm::assembler::assemble("
+                CALL wait
+                LD V1, 2
+        wait:   LD V0, K
+                RET
+    ").unwrap();
+    let mut processor = Processor::new();
+    processor.load_program(&rom).unwrap();
+
+    let state = processor.step_over();
+    assert_eq!(state.cycles, 2);
+    assert_eq!(processor.pc(), 0x204);
+    assert_eq!(processor.sp(), 1);
+}
+
+#[test]
+fn step_over_stops_when_the_call_waits_for_the_next_frame() {
+    let rom = chipvm::assembler::assemble("
+                CALL draw
+                LD V1, 2
+        draw:   DRW V0, V0, 1
+                RET
+    ").unwrap();
+    let quirks = chipvm::quirks::Quirks { display_wait: true, ..chipvm::quirks::Quirks::default() };
+    let mut processor = Processor::with_quirks(quirks);
+    processor.load_program(&rom).unwrap();
+
+    processor.step_over();
+    assert_eq!(processor.pc(), 0x206);
+    assert_eq!(processor.sp(), 1);
+}
+
+#[test]
+fn exit_halts_the_vm() {
+    let rom = chipvm::assembler::assemble("
+        LD V0, 1
+        EXIT
+        LD V0, 2
+    ").unwrap();
+    let mut processor = Processor::new();
+    processor.load_program(&rom).unwrap();
+
+    assert!(processor.tick([false; 16]).halted);
+    assert_eq!(processor.pc(), 0x202);
+
+    let state = processor.tick([false; 16]);
+    assert!(state.halted);
+    assert_eq!(state.cycles, 0);
+    assert_eq!(processor.pc(), 0x202);
+    assert_eq!(processor.register(0), 1);
+}
+
+#[test]
+fn call_stack_holds_the_active_return_addresses() {
+    let mut processor = Processor::new();
+    processor.load_program(&PROGRAM).unwrap();
+    assert!(processor.call_stack().is_empty());
+
+    processor.step();
+    processor.step();
+    assert_eq!(processor.pc(), 0x20a);
+    assert_eq!(processor.call_stack(), &[0x202, 0x208]);
+
+    processor.step();
+    processor.step();
+    assert_eq!(processor.call_stack(), &[0x202]);
+}
+
+#[test]
+fn step_executes_one_opcode_at_a_time() {
+    let mut processor = Processor::new();
+    processor.load_program(&[0x60, 0x05, 0x70, 0x03]).unwrap();
+    processor.set_delay_timer(10);
+    assert_eq!(processor.current_opcode(), 0x6005);
+
+    processor.step();
+    assert_eq!(processor.pc(), 0x202);
+    assert_eq!(processor.register(0), 5);
+    assert_eq!(processor.current_opcode(), 0x7003);
+
+    processor.step();
+    assert_eq!(processor.pc(), 0x204);
+    assert_eq!(processor.register(0), 8);
+    assert_eq!(processor.delay_timer(), 10);
+}
+
+#[test]
+fn breakpoint_halts_before_the_opcode_there() {
+    let mut processor = Processor::new();
+    // LD V0, 1; LD V1, 2; JP 204
+    processor.load_program(&[0x60, 0x01, 0x61, 0x02, 0x12, 0x04]).unwrap();
+    processor.add_breakpoint(0x202);
+
+    let state = processor.tick([false; 16]);
+    assert_eq!(state.hit_breakpoint, Some(0x202));
+    assert_eq!(processor.pc(), 0x202);
+    assert_eq!((processor.register(0), processor.register(1)), (1, 0));
+
+    let state = processor.step();
+    assert_eq!(state.hit_breakpoint, None);
+    assert_eq!(processor.register(1), 2);
+}
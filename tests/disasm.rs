@@ -0,0 +1,50 @@
+use chipvm::disasm::{disassemble_opcode, disassemble_rom};
+
+#[test]
+fn known_opcodes_disassemble_to_their_mnemonics() {
+    let cases = [
+        (0x00E0, "CLS"),
+        (0x00EE, "RET"),
+        (0x1234, "JP 0x234"),
+        (0x2ABC, "CALL 0xABC"),
+        (0x3A2A, "SE VA, 0x2A"),
+        (0x5120, "SE V1, V2"),
+        (0x632A, "LD V3, 0x2A"),
+        (0x8124, "ADD V1, V2"),
+        (0x812E, "SHL V1, V2"),
+        (0xA123, "LD I, 0x123"),
+        (0xB300, "JP V0, 0x300"),
+        (0xC70F, "RND V7, 0x0F"),
+        (0xD015, "DRW V0, V1, 5"),
+        (0xE59E, "SKP V5"),
+        (0xF40A, "LD V4, K"),
+        (0xF233, "LD B, V2"),
+        (0xF865, "LD V8, [I]"),
+    ];
+
+    for &(opcode, text) in cases.iter() {
+        assert_eq!(disassemble_opcode(opcode), text, "{:#06X}", opcode);
+    }
+}
+
+#[test]
+fn unknown_opcodes_disassemble_to_words() {
+    assert_eq!(disassemble_opcode(0x5121), "DW 0x5121");
+    assert_eq!(disassemble_opcode(0xE1FF), "DW 0xE1FF");
+}
+
+#[test]
+fn roms_disassemble_with_their_addresses() {
+    assert_eq!(
+        disassemble_rom(&[0x60, 0x05, 0x12, 0x00, 0xAB]),
+        vec![(0x200, "LD V0, 0x05".to_string()), (0x202, "JP 0x200".to_string()), (0x204, "DB 0xAB".to_string())]
+    );
+}
+
+#[test]
+fn long_loads_disassemble_as_one_instruction() {
+    assert_eq!(
+        disassemble_rom(&[0xF0, 0x00, 0xBE, 0xEF, 0xF0, 0x00, 0x12]),
+        vec![(0x200, "LD I, long 0xBEEF".to_string()), (0x204, "DW 0xF000".to_string()), (0x206, "DB 0x12".to_string())]
+    );
+}
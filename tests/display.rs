@@ -0,0 +1,186 @@
+#![cfg(feature = "sdl")]
+
+use chipvm::display::{grid_lines, inverted, render, toast_lines, toast_pixels, TOAST_COLUMNS, DEFAULT_BG_COLOR, DEFAULT_FG_COLOR, DEFAULT_OVERLAP_COLOR, DEFAULT_PLANE2_COLOR};
+use chipvm::assembler::assemble;
+use chipvm::output::{Vram, VRAM_HEIGHT, VRAM_WIDTH};
+use chipvm::Processor;
+use sdl2::rect::Rect;
+
+const PALETTE: [sdl2::pixels::Color; 4] = [DEFAULT_BG_COLOR, DEFAULT_FG_COLOR, DEFAULT_PLANE2_COLOR, DEFAULT_OVERLAP_COLOR];
+
+fn rgb(color: sdl2::pixels::Color) -> image::Rgb<u8> {
+    image::Rgb([color.r, color.g, color.b])
+}
+
+#[test]
+fn inverting_swaps_set_and_unset_pixel_colors() {
+    let mut vram = [[0; VRAM_WIDTH]; VRAM_HEIGHT];
+    vram[0][0] = 1;
+
+    let normal = render(&vram, false, 1, PALETTE);
+    assert_eq!(*normal.get_pixel(0, 0), rgb(DEFAULT_FG_COLOR));
+    assert_eq!(*normal.get_pixel(1, 0), rgb(DEFAULT_BG_COLOR));
+
+    let inverted = render(&vram, false, 1, inverted(PALETTE));
+    assert_eq!(*inverted.get_pixel(0, 0), rgb(DEFAULT_BG_COLOR));
+    assert_eq!(*inverted.get_pixel(1, 0), rgb(DEFAULT_FG_COLOR));
+}
+
+#[test]
+fn pixels_are_scaled_to_fill_the_window() {
+    let mut vram = [[0; VRAM_WIDTH]; VRAM_HEIGHT];
+    vram[1][2] = 1;
+
+    let image = render(&vram, false, 3, PALETTE);
+    assert_eq!(image.dimensions(), (64 * 3, 32 * 3));
+    let lit: Vec<(u32, u32)> = image.enumerate_pixels().filter(|(_, _, &p)| p == rgb(DEFAULT_FG_COLOR)).map(|(x, y, _)| (x, y)).collect();
+    assert_eq!(lit, [(6, 3), (7, 3), (8, 3), (6, 4), (7, 4), (8, 4), (6, 5), (7, 5), (8, 5)]);
+
+    // High resolution pixels are 1.5 window pixels wide at scale 3, so their edges are rounded down
+    let image = render(&vram, true, 3, PALETTE);
+    assert_eq!(image.dimensions(), (64 * 3, 32 * 3));
+    let lit: Vec<(u32, u32)> = image.enumerate_pixels().filter(|(_, _, &p)| p == rgb(DEFAULT_FG_COLOR)).map(|(x, y, _)| (x, y)).collect();
+    assert_eq!(lit, [(3, 1), (3, 2)]);
+}
+
+#[test]
+fn screenshots_use_the_configured_colors() {
+    let fg = sdl2::pixels::Color::RGB(255, 255, 255);
+    let bg = sdl2::pixels::Color::RGB(0, 0, 64);
+    let mut vram = [[0; VRAM_WIDTH]; VRAM_HEIGHT];
+    // A checkerboard in the top left corner
+    vram[0][0] = 1;
+    vram[1][1] = 1;
+
+    let image = render(&vram, false, 2, [bg, fg, DEFAULT_PLANE2_COLOR, DEFAULT_OVERLAP_COLOR]);
+
+    assert_eq!(*image.get_pixel(1, 1), rgb(fg));
+    assert_eq!(*image.get_pixel(2, 1), rgb(bg));
+    assert_eq!(*image.get_pixel(1, 2), rgb(bg));
+    assert_eq!(*image.get_pixel(3, 3), rgb(fg));
+    assert_eq!(*image.get_pixel(127, 63), rgb(bg));
+}
+
+#[test]
+fn grid_lines_fall_between_pixels() {
+    let lines = grid_lines(64, 32, 10);
+
+    assert_eq!(lines.len(), 63 + 31);
+    assert_eq!(lines[0], Rect::new(10, 0, 1, 320));
+    assert_eq!(lines[62], Rect::new(630, 0, 1, 320));
+    assert_eq!(lines[63], Rect::new(0, 10, 640, 1));
+    assert_eq!(lines[93], Rect::new(0, 310, 640, 1));
+}
+
+#[test]
+fn grid_is_hidden_for_small_pixels() {
+    assert!(grid_lines(64, 32, 3).is_empty());
+    assert!(grid_lines(128, 64, 6).is_empty());
+    assert_eq!(grid_lines(128, 64, 8).len(), 127 + 63);
+}
+
+#[test]
+fn toasts_wrap_between_words() {
+    assert_eq!(toast_lines("Paused"), vec!["Paused"]);
+    assert_eq!(
+        toast_lines("Saved screenshot to screenshot-1700000000.png"),
+        vec!["Saved screenshot to", "screenshot-1700000000.png"]
+    );
+}
+
+#[test]
+fn toasts_split_words_too_long_for_a_line() {
+    let word = "X".repeat(TOAST_COLUMNS + 3);
+
+    assert_eq!(toast_lines(&format!("A {}", word)), vec!["A".to_string(), "X".repeat(TOAST_COLUMNS), "XXX".to_string()]);
+}
+
+#[test]
+fn toasts_sit_in_the_bottom_left_corner() {
+    // The top row of P is 0xF0, its bottom row 0x80
+    let pixels = toast_pixels(&toast_lines("P"));
+
+    assert!((1..5).all(|x| pixels.contains(&(x, VRAM_HEIGHT - 6))));
+    assert!(pixels.contains(&(1, VRAM_HEIGHT - 2)));
+    assert!(!pixels.contains(&(2, VRAM_HEIGHT - 2)));
+    assert!(pixels.iter().all(|&(x, y)| (1..5).contains(&x) && (VRAM_HEIGHT - 6..VRAM_HEIGHT - 1).contains(&y)));
+
+    let two_lines = toast_pixels(&toast_lines(&"P".repeat(TOAST_COLUMNS + 1)));
+    assert!(two_lines.contains(&(1, VRAM_HEIGHT - 12)));
+    assert!(two_lines.contains(&(1 + (TOAST_COLUMNS - 1) * 5, VRAM_HEIGHT - 12)));
+    assert!(two_lines.contains(&(1, VRAM_HEIGHT - 6)));
+}
+
+/// Pixels as (x, y)
+type Pixels = Vec<(usize, usize)>;
+
+/// Lit pixels of the vram
+fn lit(vram: &Vram) -> Pixels {
+    let mut pixels = Vec::new();
+    for (y, row) in vram.iter().enumerate() {
+        for (x, &pixel) in row.iter().enumerate() {
+            if pixel != 0 {
+                pixels.push((x, y));
+            }
+        }
+    }
+
+    pixels
+}
+
+/// Draws the 0 glyph in the top left and bottom right corners, then runs `scroll`. Returns
+/// the lit pixels before and after the scroll
+fn scroll(hires: bool, scroll: &str) -> (Pixels, Pixels) {
+    let (width, height) = if hires { (128, 64) } else { (64, 32) };
+    let source = format!("
+        {}
+        LD F, V0
+        DRW V0, V0, 5
+        LD V1, {}
+        LD V2, {}
+        DRW V1, V2, 5
+        {}
+    ", if hires { "HIGH" } else { "LOW" }, width - 4, height - 5, scroll);
+    let mut processor = Processor::new();
+    processor.load_program(&assemble(&source).unwrap()).unwrap();
+    for _ in 0..6 {
+        processor.step();
+    }
+
+    let before = lit(&processor.vram);
+    assert_eq!(before.len(), 2 * 14);
+    processor.step();
+
+    (before, lit(&processor.vram))
+}
+
+/// `pixels` moved by (dx, dy), dropping those that leave the screen
+fn moved(pixels: &[(usize, usize)], (dx, dy): (isize, isize), hires: bool) -> Pixels {
+    let (width, height) = if hires { (128, 64) } else { (64, 32) };
+    let mut moved: Pixels = pixels
+        .iter()
+        .map(|&(x, y)| (x as isize + dx, y as isize + dy))
+        .filter(|&(x, y)| x >= 0 && y >= 0 && x < width && y < height)
+        .map(|(x, y)| (x as usize, y as usize))
+        .collect();
+    moved.sort_by_key(|&(x, y)| (y, x));
+
+    moved
+}
+
+#[test]
+fn scrolling_moves_pixels_and_clears_the_edge_in_high_resolution() {
+    for &(op, offset) in [("SCD 3", (0, 3)), ("SCD 0xF", (0, 15)), ("SCR", (4, 0)), ("SCL", (-4, 0))].iter() {
+        let (before, after) = scroll(true, op);
+        assert_eq!(after, moved(&before, offset, true), "{}", op);
+    }
+}
+
+#[test]
+fn scrolling_moves_half_as_far_in_low_resolution() {
+    // Odd line counts round down, so SCD 1 doesn't move anything
+    for &(op, offset) in [("SCD 1", (0, 0)), ("SCD 3", (0, 1)), ("SCD 4", (0, 2)), ("SCR", (2, 0)), ("SCL", (-2, 0))].iter() {
+        let (before, after) = scroll(false, op);
+        assert_eq!(after, moved(&before, offset, false), "{}", op);
+    }
+}
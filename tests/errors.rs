@@ -0,0 +1,108 @@
+use chipvm::assembler::assemble;
+use chipvm::processor::{EmuError, UnknownOpcodePolicy, STACK_DEPTH};
+use chipvm::Processor;
+
+fn processor(source: &str) -> Processor {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble(source).unwrap()).unwrap();
+    processor
+}
+
+#[test]
+fn runaway_calls_overflow_the_stack() {
+    let mut processor = processor("loop: CALL loop");
+    processor.set_speed(100);
+
+    assert_eq!(processor.tick([false; 16]).error, Some(EmuError::StackOverflow));
+}
+
+#[test]
+fn return_without_call_underflows_the_stack() {
+    let mut processor = processor("RET");
+
+    assert_eq!(processor.tick([false; 16]).error, Some(EmuError::StackUnderflow));
+    assert_eq!(processor.sp(), 0);
+}
+
+#[test]
+fn unknown_opcodes_are_reported_and_skipped() {
+    // 5XY with a non-zero low nibble isn't an opcode
+    let mut processor = Processor::new();
+    processor.load_program(&[0x50, 0x01, 0x61, 0x02, 0x12, 0x04]).unwrap();
+
+    let state = processor.tick([false; 16]);
+
+    assert_eq!(state.unknown_opcode, Some((0x200, 0x5001)));
+    assert_eq!(state.error, None);
+    assert_eq!(processor.register(1), 2);
+}
+
+#[test]
+fn unknown_opcodes_halt_when_asked_to() {
+    let mut processor = Processor::new();
+    processor.load_program(&[0x50, 0x01, 0x61, 0x02]).unwrap();
+    processor.set_unknown_opcode_policy(UnknownOpcodePolicy::Halt);
+
+    let state = processor.tick([false; 16]);
+
+    assert_eq!(state.error, Some(EmuError::UnknownOpcode { pc: 0x200, opcode: 0x5001 }));
+    assert_eq!(processor.pc(), 0x200);
+    assert_eq!(processor.register(1), 0);
+}
+
+#[test]
+fn seventeen_nested_calls_overflow_the_stack() {
+    // Each CALL calls the one right after it
+    let source: String = (1..=17).map(|n| format!("CALL {:#X}\n", 0x200 + n * 2)).collect();
+    let mut processor = processor(&source);
+    processor.set_speed(100);
+
+    let state = processor.tick([false; 16]);
+
+    assert_eq!(state.error, Some(EmuError::StackOverflow));
+    assert_eq!(processor.sp(), STACK_DEPTH);
+    assert_eq!(processor.pc(), 0x200 + 16 * 2);
+}
+
+#[test]
+fn try_tick_returns_each_error() {
+    let try_tick = |mut processor: Processor| processor.try_tick([false; 16]).err();
+
+    let mut overflow = processor("loop: CALL loop");
+    overflow.set_speed(100);
+    assert_eq!(try_tick(overflow), Some(EmuError::StackOverflow));
+
+    assert_eq!(try_tick(processor("RET")), Some(EmuError::StackUnderflow));
+
+    let mut unknown = Processor::new();
+    unknown.load_program(&[0x50, 0x01]).unwrap();
+    unknown.set_unknown_opcode_policy(UnknownOpcodePolicy::Halt);
+    assert_eq!(try_tick(unknown), Some(EmuError::UnknownOpcode { pc: 0x200, opcode: 0x5001 }));
+
+    assert_eq!(try_tick(processor("LD V1, 2")), None);
+}
+
+#[test]
+fn try_tick_keeps_failing_after_an_error() {
+    let mut processor = processor("RET");
+
+    assert!(processor.try_tick([false; 16]).is_err());
+    assert_eq!(processor.try_tick([false; 16]).err(), Some(EmuError::StackUnderflow));
+}
+
+#[test]
+fn key_skips_only_use_the_low_nibble_of_vx() {
+    let mut keypad = [false; 16];
+    keypad[0] = true;
+
+    // V0 = 0x10 is read as key 0
+    let mut skp = processor("LD V0, 0x10\nSKP V0");
+    skp.set_speed(2);
+    assert!(skp.try_tick(keypad).is_ok());
+    assert_eq!(skp.pc(), 0x206);
+
+    let mut sknp = processor("LD V0, 0x10\nSKNP V0");
+    sknp.set_speed(2);
+    assert!(sknp.try_tick([false; 16]).is_ok());
+    assert_eq!(sknp.pc(), 0x206);
+}
@@ -0,0 +1,24 @@
+use chipvm::assembler::assemble;
+use chipvm::font::{font_address, font_sprite, BIG_FONT_HEIGHT, BIG_FONT_SET, BIG_FONT_START};
+use chipvm::Processor;
+
+#[test]
+fn digit_a_is_the_eleventh_glyph() {
+    assert_eq!(font_address(0xA), 50);
+    assert_eq!(font_sprite(0xA), [0xF0, 0x90, 0xF0, 0x90, 0x90]);
+
+    let processor = Processor::new();
+    assert_eq!(processor.memory_slice(50..55), Some(font_sprite(0xA)));
+}
+
+#[test]
+fn big_font_points_i_at_the_ten_byte_glyph() {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble("LD V1, 3\nLD HF, V1").unwrap()).unwrap();
+    processor.step();
+    processor.step();
+
+    assert_eq!(processor.i(), BIG_FONT_START + 30);
+    assert_eq!(processor.memory_slice(processor.i()..processor.i() + BIG_FONT_HEIGHT), Some(&BIG_FONT_SET[30..40]));
+    assert_eq!(BIG_FONT_SET[30..40], [0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C]);
+}
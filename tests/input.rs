@@ -0,0 +1,99 @@
+#![cfg(feature = "sdl")]
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chipvm::input::{Debouncer, KeyMap};
+use chipvm::timing::Clock;
+use sdl2::keyboard::Keycode;
+
+#[test]
+fn held_keys_are_all_reported() {
+    let keymap = KeyMap::default();
+
+    // Games moving diagonally read two keys held at the same time
+    let keypad = keymap.keypad(vec![Keycode::W, Keycode::D]);
+
+    let held: Vec<usize> = (0..16).filter(|&i| keypad[i]).collect();
+    assert_eq!(held, vec![0x5, 0x9]);
+}
+
+#[test]
+fn custom_mapping_resolves_its_keys() {
+    // AZERTY puts A where QWERTY has Q
+    let keymap = KeyMap::from_pairs(&[(Keycode::A, 0x4), (Keycode::Z, 0x5), (Keycode::Q, 0x7), (Keycode::Space, 0x10)]);
+
+    assert_eq!(keymap.get(Keycode::A), Some(0x4));
+    assert_eq!(keymap.get(Keycode::Z), Some(0x5));
+    assert_eq!(keymap.get(Keycode::Q), Some(0x7));
+    assert_eq!(keymap.get(Keycode::W), None);
+    assert_eq!(keymap.get(Keycode::Space), None);
+}
+
+#[test]
+fn unbound_keys_are_ignored() {
+    let keymap = KeyMap::default();
+
+    assert_eq!(keymap.keypad(vec![Keycode::P, Keycode::Tab]), [false; 16]);
+}
+
+/// A clock that only moves when the test moves it
+#[derive(Clone, Default)]
+struct FakeClock(Rc<Cell<Duration>>);
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.0.get()
+    }
+}
+
+#[test]
+fn bouncing_key_registers_once() {
+    let clock = FakeClock::default();
+    let mut debouncer = Debouncer::new(clock.clone(), Duration::from_millis(50));
+    let mut pressed = [false; 16];
+    pressed[5] = true;
+
+    // The key bounces every 10ms, which the keypad sees as a new press every other frame
+    let mut presses = 0;
+    let mut was_down = false;
+    for frame in 0..10 {
+        let keys = if frame % 2 == 0 { pressed } else { [false; 16] };
+        let down = debouncer.filter(keys)[5];
+        if down && !was_down {
+            presses += 1;
+        }
+        was_down = down;
+        clock.0.set(clock.0.get() + Duration::from_millis(10));
+    }
+    assert_eq!(presses, 1);
+
+    // Pressing it again after the window is a new press
+    debouncer.filter([false; 16]);
+    clock.0.set(clock.0.get() + Duration::from_millis(50));
+    assert!(debouncer.filter(pressed)[5]);
+}
+
+#[test]
+fn key_pressed_too_soon_stays_up_while_held() {
+    let clock = FakeClock::default();
+    let mut debouncer = Debouncer::new(clock.clone(), Duration::from_millis(50));
+    let mut pressed = [false; 16];
+    pressed[5] = true;
+
+    assert!(debouncer.filter(pressed)[5]);
+    clock.0.set(clock.0.get() + Duration::from_millis(10));
+    debouncer.filter([false; 16]);
+
+    // Pressed again inside the window and held well past it
+    for _ in 0..20 {
+        clock.0.set(clock.0.get() + Duration::from_millis(10));
+        assert!(!debouncer.filter(pressed)[5]);
+    }
+
+    // Only a release and a later press count
+    debouncer.filter([false; 16]);
+    clock.0.set(clock.0.get() + Duration::from_millis(50));
+    assert!(debouncer.filter(pressed)[5]);
+}
@@ -0,0 +1,112 @@
+use chipvm::assembler::assemble;
+use chipvm::Processor;
+
+fn waiting_processor() -> Processor {
+    let rom = assemble("
+                LD V3, K
+        done:   JP done
+    ").unwrap();
+
+    let mut processor = Processor::new();
+    processor.load_program(&rom).unwrap();
+    processor
+}
+
+fn keypad(key: usize) -> [bool; 16] {
+    let mut keypad = [false; 16];
+    keypad[key] = true;
+    keypad
+}
+
+#[test]
+fn key_held_before_the_wait_is_stored_once_released() {
+    let mut processor = waiting_processor();
+
+    for _ in 0..3 {
+        processor.tick(keypad(5));
+        assert_eq!(processor.pc(), 0x200);
+        assert!(processor.keypresswait);
+    }
+
+    processor.tick([false; 16]);
+    assert_eq!(processor.register(3), 5);
+    assert_eq!(processor.pc(), 0x202);
+    assert!(!processor.keypresswait);
+}
+
+#[test]
+fn wait_stays_on_fx0a_without_a_key() {
+    let mut processor = waiting_processor();
+
+    processor.tick([false; 16]);
+    assert_eq!(processor.pc(), 0x200);
+
+    processor.tick(keypad(0xa));
+    processor.tick([false; 16]);
+    assert_eq!(processor.register(3), 0xa);
+    assert_eq!(processor.pc(), 0x202);
+}
+
+#[test]
+fn breakpoint_on_fx0a_is_hit_once() {
+    let mut processor = waiting_processor();
+    processor.add_breakpoint(0x200);
+
+    assert_eq!(processor.tick([false; 16]).hit_breakpoint, Some(0x200));
+    assert_eq!(processor.tick([false; 16]).hit_breakpoint, None);
+    assert_eq!(processor.tick([false; 16]).hit_breakpoint, None);
+}
+
+#[test]
+fn wait_ends_the_frame() {
+    let rom = assemble("
+                LD V0, 1
+                LD V1, 2
+                LD V3, K
+                LD V4, 4
+        done:   JP done
+    ").unwrap();
+    let mut processor = Processor::new();
+    processor.load_program(&rom).unwrap();
+    processor.set_speed(1000);
+
+    processor.tick([false; 16]);
+
+    assert_eq!(processor.pc(), 0x204);
+    assert_eq!((processor.register(0), processor.register(1), processor.register(4)), (1, 2, 0));
+    assert_eq!(processor.opcode_stats()["FX0A"], 1);
+}
+
+#[test]
+fn keypad_reflects_the_last_tick() {
+    let mut processor = waiting_processor();
+    let mut keys = keypad(0x2);
+    keys[0xc] = true;
+
+    processor.tick(keys);
+    assert_eq!(processor.keypad(), keys);
+
+    processor.tick([false; 16]);
+    assert_eq!(processor.keypad(), [false; 16]);
+}
+
+#[test]
+fn register_is_written_once_on_release() {
+    let rom = assemble("
+                LD V3, 0xEE
+                LD V3, K
+        done:   JP done
+    ").unwrap();
+    let mut processor = Processor::new();
+    processor.load_program(&rom).unwrap();
+
+    processor.tick(keypad(5));
+    assert_eq!(processor.register(3), 0xEE);
+
+    processor.tick([false; 16]);
+    assert_eq!(processor.register(3), 5);
+
+    processor.tick(keypad(7));
+    processor.tick([false; 16]);
+    assert_eq!(processor.register(3), 5);
+}
@@ -0,0 +1,101 @@
+use chipvm::assembler::assemble;
+use chipvm::processor::{LoadError, OutOfBounds, ETI660_PROGRAM_START, MEMORY_SIZE, PROGRAM_START};
+use chipvm::{Processor, FONT_SET};
+
+fn run(source: &str) -> Processor {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble(source).unwrap()).unwrap();
+    processor.tick([false; 16]);
+    processor
+}
+
+#[test]
+fn oversized_rom_is_rejected() {
+    let mut processor = Processor::new();
+
+    match processor.load_program(&[0; 4000]) {
+        Err(e @ LoadError::TooLarge { len: 4000, available: 3584 }) => {
+            assert_eq!(e.to_string(), "program is 4000 bytes but only 3584 bytes of memory are available");
+        }
+        other => panic!("expected TooLarge, got {:?}", other)
+    }
+}
+
+#[test]
+fn loads_and_stores_near_the_top_of_memory_wrap_around() {
+    let processor = run("LD I, 0xFFE\nLD V3, [I]\ndone: JP done");
+    assert_eq!(processor.registers()[..4], [0, 0, FONT_SET[0], FONT_SET[1]]);
+
+    let processor = run("LD V0, 1\nLD V1, 2\nLD V2, 3\nLD I, 0xFFF\nLD [I], V2\ndone: JP done");
+    assert_eq!(processor.read_memory(0xFFF), Some(1));
+    assert_eq!(processor.memory_slice(0..2), Some(&[2, 3][..]));
+}
+
+#[test]
+fn sprites_near_the_top_of_memory_wrap_around() {
+    let processor = run("LD I, 0xFFD\nDRW V0, V0, 5\ndone: JP done");
+
+    assert_eq!(processor.error, None);
+    assert_eq!(processor.pc(), 0x204);
+}
+
+#[test]
+fn opcode_at_the_top_of_memory_wraps_around() {
+    let mut processor = Processor::new();
+    processor.write_memory(0xFFF, 0x60).unwrap();
+    processor.set_pc(0xFFF).unwrap();
+
+    // The low byte comes from address 0, the first byte of the font
+    assert_eq!(processor.current_opcode(), 0x6000 | FONT_SET[0] as u16);
+
+    processor.step();
+    assert_eq!(processor.register(0), FONT_SET[0]);
+}
+
+#[test]
+fn memory_access_is_bounds_checked() {
+    let mut processor = Processor::new();
+
+    assert_eq!(processor.write_memory(0x300, 0x42), Ok(()));
+    assert_eq!(processor.read_memory(0x300), Some(0x42));
+    assert_eq!(processor.memory_slice(0x2FF..0x301), Some(&[0, 0x42][..]));
+    assert_eq!(processor.read_memory(0xFFF), Some(0));
+
+    assert_eq!(processor.write_memory(0x1000, 1), Err(OutOfBounds(0x1000)));
+    assert_eq!(processor.read_memory(0x1000), None);
+    assert_eq!(processor.memory_slice(0xFFF..0x1001), None);
+}
+
+#[test]
+fn programs_load_at_the_eti660_address() {
+    let mut processor = Processor::new();
+    processor.load_program_at(&[0x60, 0x05, 0x16, 0x02], ETI660_PROGRAM_START).unwrap();
+
+    assert_eq!(processor.pc(), 0x600);
+    assert_eq!(processor.memory_slice(0x600..0x604), Some(&[0x60, 0x05, 0x16, 0x02][..]));
+    assert_eq!(processor.read_memory(0x200), Some(0));
+
+    processor.tick([false; 16]);
+    assert_eq!(processor.register(0), 5);
+
+    processor.reset();
+    assert_eq!(processor.pc(), 0x600);
+
+    assert!(matches!(
+        processor.load_program_at(&[0; 0xA01], ETI660_PROGRAM_START),
+        Err(LoadError::TooLarge { len: 0xA01, available: 0xA00 })
+    ));
+    assert!(matches!(processor.load_program_at(&[0; 2], 0x1000), Err(LoadError::BadAddress(0x1000))));
+}
+
+#[test]
+fn memory_is_never_smaller_than_the_classic_4k() {
+    let mut processor = Processor::with_memory_size(16);
+
+    assert_eq!(processor.memory.len(), MEMORY_SIZE);
+    assert_eq!(processor.memory_slice(0..FONT_SET.len()), Some(&FONT_SET[..]));
+    assert!(matches!(
+        processor.reload_program(&[0; 4000]),
+        Err(LoadError::TooLarge { len: 4000, available }) if available == MEMORY_SIZE - PROGRAM_START
+    ));
+}
@@ -0,0 +1,98 @@
+use chipvm::assembler::assemble;
+use chipvm::Processor;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Loads `source` and executes its first `steps` opcodes
+fn run(source: &str, steps: usize) -> Processor {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble(source).unwrap()).unwrap();
+    for _ in 0..steps {
+        processor.step();
+    }
+
+    processor
+}
+
+#[test]
+fn register_skips_compare_for_equality() {
+    // SE skips when the registers match, SNE when they differ
+    assert_eq!(run("LD V1, 7\nLD V2, 7\nSE V1, V2", 3).pc(), 0x204 + 4);
+    assert_eq!(run("LD V1, 7\nLD V2, 8\nSE V1, V2", 3).pc(), 0x204 + 2);
+    assert_eq!(run("LD V1, 7\nLD V2, 8\nSNE V1, V2", 3).pc(), 0x204 + 4);
+    assert_eq!(run("LD V1, 7\nLD V2, 7\nSNE V1, V2", 3).pc(), 0x204 + 2);
+}
+
+#[test]
+fn add_immediate_leaves_vf_alone() {
+    let processor = run("LD VF, 0x5A\nLD V1, 0xFF\nADD V1, 2", 3);
+
+    assert_eq!(processor.register(1), 1);
+    assert_eq!(processor.register(0xf), 0x5A);
+}
+
+#[test]
+fn shift_left_moves_on_to_the_next_opcode() {
+    let processor = run("SHL V1", 1);
+
+    assert_eq!(processor.pc(), 0x202);
+}
+
+#[test]
+fn draw_sets_vf_only_if_a_pixel_was_erased() {
+    let source = "
+        LD VF, 0x5A
+        LD F, V0
+        DRW V0, V0, 5   ; nothing to erase
+        LD V2, VF
+        LD V1, 3
+        DRW V1, V1, 5   ; overlaps the bottom right corner of the first 0
+        LD V3, VF
+        LD V1, 20
+        DRW V1, V0, 5   ; draws next to it
+        LD V4, VF
+    ";
+    let processor = run(source, 10);
+
+    assert_eq!((processor.register(2), processor.register(3), processor.register(4)), (0, 1, 0));
+}
+
+#[test]
+fn random_byte_comes_from_the_seeded_rng_and_is_masked() {
+    let mut processor = Processor::with_seed(42);
+    processor.load_program(&assemble("RND V3, 0x0F").unwrap()).unwrap();
+    processor.step();
+
+    let expected = StdRng::seed_from_u64(42).gen::<u8>() & 0x0F;
+    assert_eq!(processor.register(3), expected);
+}
+
+#[test]
+fn resolution_follows_the_display_mode() {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble("HIGH\nLOW").unwrap()).unwrap();
+    processor.set_speed(1);
+
+    assert_eq!(processor.tick([false; 16]).resolution, (128, 64));
+    assert_eq!(processor.tick([false; 16]).resolution, (64, 32));
+}
+
+#[test]
+fn arithmetic_on_vf_keeps_the_flag() {
+    // The flag is written after the result, so it's what's left in VF
+    assert_eq!(run("LD VF, 0xF0\nLD V1, 0x20\nADD VF, V1", 3).register(0xf), 1);
+    assert_eq!(run("LD VF, 0x10\nLD V1, 0x20\nADD VF, V1", 3).register(0xf), 0);
+    assert_eq!(run("LD VF, 0x30\nLD V1, 0x20\nSUB VF, V1", 3).register(0xf), 1);
+    assert_eq!(run("LD VF, 0x10\nLD V1, 0x20\nSUB VF, V1", 3).register(0xf), 0);
+    assert_eq!(run("LD VF, 0x10\nLD V1, 0x20\nSUBN VF, V1", 3).register(0xf), 1);
+    assert_eq!(run("LD VF, 0x30\nLD V1, 0x20\nSUBN VF, V1", 3).register(0xf), 0);
+}
+
+#[test]
+fn subtracting_equal_values_doesnt_borrow() {
+    let processor = run("LD V1, 0x20\nLD V2, 0x20\nSUB V1, V2", 3);
+    assert_eq!((processor.register(1), processor.register(0xf)), (0, 1));
+
+    let processor = run("LD V1, 0x20\nLD V2, 0x20\nSUBN V1, V2", 3);
+    assert_eq!((processor.register(1), processor.register(0xf)), (0, 1));
+}
@@ -0,0 +1,139 @@
+use chipvm::assembler::assemble;
+use chipvm::quirks::{Platform, Quirks};
+use chipvm::Processor;
+
+/// Runs `source` for a frame with V1 = 0b0110_0001 and V2 = 0b1000_0110 and returns V1 and VF
+fn shift(source: &str, shift_uses_vy: bool) -> (u8, u8) {
+    let rom = assemble(&format!("LD V1, 0b01100001\nLD V2, 0b10000110\n{}\ndone: JP done", source)).unwrap();
+    let mut processor = Processor::with_quirks(Quirks { shift_uses_vy, ..Quirks::default() });
+    processor.load_program(&rom).unwrap();
+
+    processor.tick([false; 16]);
+    (processor.register(1), processor.register(0xf))
+}
+
+#[test]
+fn shift_right_shifts_vx_in_place() {
+    assert_eq!(shift("SHR V1, V2", false), (0b0011_0000, 1));
+}
+
+#[test]
+fn shift_right_uses_vy_with_the_quirk() {
+    assert_eq!(shift("SHR V1, V2", true), (0b0100_0011, 0));
+}
+
+#[test]
+fn shift_left_shifts_vx_in_place() {
+    assert_eq!(shift("SHL V1, V2", false), (0b1100_0010, 0));
+}
+
+#[test]
+fn shift_left_uses_vy_with_the_quirk() {
+    assert_eq!(shift("SHL V1, V2", true), (0b0000_1100, 1));
+}
+
+#[test]
+fn each_platform_has_its_documented_quirks() {
+    let quirks = |shift_uses_vy, load_store_increments_i, jump_uses_vx, sprite_wrap, display_wait| Quirks {
+        shift_uses_vy,
+        load_store_increments_i,
+        jump_uses_vx,
+        fx1e_sets_vf: false,
+        sprite_wrap,
+        display_wait
+    };
+
+    assert_eq!(Quirks::for_platform(Platform::CosmacVip), quirks(true, true, false, false, true));
+    assert_eq!(Quirks::for_platform(Platform::Chip48), quirks(false, false, true, false, false));
+    assert_eq!(Quirks::for_platform(Platform::SuperChip), quirks(false, false, true, false, false));
+    assert_eq!(Quirks::for_platform(Platform::XoChip), quirks(true, true, false, true, false));
+}
+
+#[test]
+fn platforms_are_looked_up_by_name() {
+    assert_eq!(Platform::from_name("superchip"), Some(Platform::SuperChip));
+    assert_eq!(Platform::from_name("VIP"), Some(Platform::CosmacVip));
+    assert_eq!(Platform::from_name("chip48"), Some(Platform::Chip48));
+    assert_eq!(Platform::from_name("xochip"), Some(Platform::XoChip));
+    assert_eq!(Platform::from_name("megachip"), None);
+}
+
+/// Loads `source` with the quirks and executes its first `steps` opcodes
+fn run(source: &str, quirks: Quirks, steps: usize) -> Processor {
+    let mut processor = Processor::with_quirks(quirks);
+    processor.load_program(&assemble(source).unwrap()).unwrap();
+    for _ in 0..steps {
+        processor.step();
+    }
+
+    processor
+}
+
+#[test]
+fn jump_with_offset_adds_v0_or_vx() {
+    // B300 is JP V0, 0x300 to chip-8 and JP V3, 0x300 to SUPER-CHIP
+    let source = "LD V0, 0x10\nLD V3, 0x20\nJP V0, 0x300";
+
+    assert_eq!(run(source, Quirks::default(), 3).pc(), 0x310);
+    assert_eq!(run(source, Quirks { jump_uses_vx: true, ..Quirks::default() }, 3).pc(), 0x320);
+}
+
+#[test]
+fn add_to_i_only_touches_vf_with_the_quirk() {
+    let fx1e_sets_vf = Quirks { fx1e_sets_vf: true, ..Quirks::default() };
+    let overflow = "LD VF, 0x5A\nLD V1, 2\nLD I, 0xFFF\nADD I, V1";
+    let no_overflow = "LD VF, 0x5A\nLD V1, 2\nLD I, 0xFFD\nADD I, V1";
+
+    let processor = run(overflow, Quirks::default(), 4);
+    assert_eq!((processor.i(), processor.register(0xf)), (0x1001, 0x5A));
+
+    let processor = run(overflow, fx1e_sets_vf, 4);
+    assert_eq!((processor.i(), processor.register(0xf)), (0x1001, 1));
+
+    let processor = run(no_overflow, fx1e_sets_vf, 4);
+    assert_eq!((processor.i(), processor.register(0xf)), (0xFFF, 0));
+}
+
+#[test]
+fn load_moves_i_past_the_registers_only_with_the_quirk() {
+    let source = "LD I, 0x300\nLD V3, [I]";
+
+    let processor = run(source, Quirks { load_store_increments_i: true, ..Quirks::default() }, 2);
+    assert_eq!(processor.i(), 0x304);
+
+    let processor = run(source, Quirks { load_store_increments_i: false, ..Quirks::default() }, 2);
+    assert_eq!(processor.i(), 0x300);
+}
+
+#[test]
+fn sprites_off_the_right_edge_are_clipped_or_wrapped() {
+    // The top row of the 0 glyph is 0b1111_0000, drawn from x = 62
+    let source = "LD V0, 62\nLD V1, 0\nLD F, V1\nDRW V0, V1, 5";
+
+    let clipped = run(source, Quirks::default(), 4);
+    assert_eq!(clipped.vram[0][60..64], [0, 0, 1, 1]);
+    assert_eq!(clipped.vram[0][..2], [0, 0]);
+
+    let wrapped = run(source, Quirks { sprite_wrap: true, ..Quirks::default() }, 4);
+    assert_eq!(wrapped.vram[0][60..64], [0, 0, 1, 1]);
+    assert_eq!(wrapped.vram[0][..3], [1, 1, 0]);
+}
+
+#[test]
+fn display_wait_draws_at_most_one_sprite_per_frame() {
+    let source = "
+        loop:   DRW V0, V0, 1
+                ADD V1, 1
+                JP loop
+    ";
+    let count_draws = |display_wait: bool| {
+        let mut processor = Processor::with_quirks(Quirks { display_wait, ..Quirks::default() });
+        processor.load_program(&assemble(source).unwrap()).unwrap();
+        processor.set_speed(30);
+        processor.tick([false; 16]);
+        processor.opcode_stats().get("DXYN").copied()
+    };
+
+    assert_eq!(count_draws(false), Some(10));
+    assert_eq!(count_draws(true), Some(1));
+}
@@ -0,0 +1,99 @@
+use chipvm::assembler::assemble;
+use chipvm::{Processor, FONT_SET};
+
+#[test]
+fn reload_replaces_the_old_program() {
+    let old = assemble("
+                LD V0, 0x42
+                LD I, 0x300
+                LD [I], V0
+                CALL sub
+        sub:    JP sub
+    ").unwrap();
+    let new = assemble("LD V1, 7").unwrap();
+
+    let mut processor = Processor::new();
+    processor.load_program(&old).unwrap();
+    processor.tick([false; 16]);
+    assert_eq!(processor.read_memory(0x300), Some(0x42));
+
+    processor.reload_program(&new).unwrap();
+
+    assert_eq!(processor.pc(), 0x200);
+    assert_eq!(processor.sp(), 0);
+    assert_eq!(processor.i(), 0);
+    assert_eq!(processor.registers(), &[0; 16]);
+    assert_eq!(processor.memory_slice(0x200..0x202), Some(&new[..]));
+    assert!(processor.memory_slice(0x202..0x1000).unwrap().iter().all(|&b| b == 0));
+    assert_eq!(processor.memory_slice(0..FONT_SET.len()), Some(&FONT_SET[..]));
+
+    processor.tick([false; 16]);
+    assert_eq!(processor.register(1), 7);
+}
+
+#[test]
+fn reload_keeps_the_old_program_if_the_new_one_is_too_large() {
+    let mut processor = Processor::new();
+    processor.load_program(&[0x60, 0x01]).unwrap();
+
+    assert!(processor.reload_program(&[0; 4096]).is_err());
+    assert_eq!(processor.memory_slice(0x200..0x202), Some(&[0x60, 0x01][..]));
+}
+
+#[test]
+fn odd_length_programs_are_padded_with_zero() {
+    let mut processor = Processor::new();
+    processor.load_program(&[0xFF; 4]).unwrap();
+
+    // LD V0, 5 followed by a lone 0x12, which has to run as JP 0x200
+    processor.load_program(&[0x60, 0x05, 0x12]).unwrap();
+    assert_eq!(processor.read_memory(0x203), Some(0));
+
+    processor.step();
+    processor.step();
+    assert_eq!(processor.register(0), 5);
+    assert_eq!(processor.pc(), 0x200);
+}
+
+#[test]
+fn an_odd_length_program_at_the_top_of_memory_wraps_its_last_fetch() {
+    let mut rom = vec![0; 4096 - 0x201];
+    *rom.last_mut().unwrap() = 0x12;
+
+    let mut processor = Processor::new();
+    processor.load_program_at(&rom, 0x201).unwrap();
+    processor.set_pc(0xFFF).unwrap();
+
+    assert_eq!(processor.current_opcode(), 0x1200 | FONT_SET[0] as u16);
+}
+
+#[test]
+fn reset_restores_the_power_on_state_but_keeps_the_program() {
+    let rom = assemble("
+                LD V0, 9
+                LD DT, V0
+                LD ST, V0
+                LD I, 0x300
+                LD [I], V0
+                LD F, V0
+                DRW V0, V0, 5
+                CALL sub
+        sub:    JP sub
+    ").unwrap();
+    let mut processor = Processor::new();
+    processor.load_program(&rom).unwrap();
+    processor.tick([true; 16]);
+    processor.write_memory(0, 0xFF).unwrap();
+
+    processor.reset();
+
+    assert_eq!(processor.pc(), 0x200);
+    assert_eq!(processor.i(), 0);
+    assert_eq!(processor.sp(), 0);
+    assert_eq!(processor.registers(), &[0; 16]);
+    assert_eq!((processor.delay_timer(), processor.sound_timer()), (0, 0));
+    assert_eq!(processor.keypad(), [false; 16]);
+    assert!(processor.vram.iter().flatten().all(|&pixel| pixel == 0));
+    assert_eq!(processor.memory_slice(0..FONT_SET.len()), Some(&FONT_SET[..]));
+    assert_eq!(processor.memory_slice(0x200..0x200 + rom.len()), Some(&rom[..]));
+}
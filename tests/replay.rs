@@ -0,0 +1,41 @@
+//! Records a scripted session to a file and checks that replaying it ends on the same screen
+
+use chipvm::replay::{InputPlayer, InputRecorder};
+use chipvm::Processor;
+
+const FRAMES: usize = 300;
+
+fn run(rom: &[u8], seed: u64, mut input: impl FnMut(usize) -> [bool; 16]) -> chipvm::output::Vram {
+    let mut processor = Processor::with_seed(seed);
+    processor.load_program(rom).unwrap();
+
+    let mut vram = processor.vram;
+    for frame in 0..FRAMES {
+        vram = *processor.tick(input(frame)).vram;
+    }
+
+    vram
+}
+
+#[test]
+fn replay_matches_recording() {
+    let rom = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/games/BRIX")).unwrap();
+    let path = std::env::temp_dir().join("chipvm-replay-test.txt");
+    let path = path.to_str().unwrap();
+
+    let mut recorder = InputRecorder::record_to(path, 1234).unwrap();
+    let recorded = run(&rom, 1234, |frame| {
+        let mut keypad = [false; 16];
+        keypad[if frame % 40 < 20 { 4 } else { 6 }] = true;
+        recorder.record(keypad).unwrap();
+        keypad
+    });
+    recorder.finish().unwrap();
+
+    let mut player = InputPlayer::replay_from(path).unwrap();
+    let seed = player.seed();
+    let replayed = run(&rom, seed, |_| player.next_frame());
+
+    std::fs::remove_file(path).unwrap();
+    assert!(recorded[..] == replayed[..]);
+}
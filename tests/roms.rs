@@ -0,0 +1,48 @@
+//! Runs the bundled ROMs through the headless harness and compares the final screen against
+//! the golden frames in `tests/golden`.
+//!
+//! `tests/roms/opcodes.ch8` runs one check per opcode behaviour and draws its number with the
+//! font if it passes, so the golden frame shows the digits 0 to C in a row. Set
+//! `UPDATE_GOLDEN=1` to rewrite the golden frames after an intended change.
+
+use chipvm::headless::run_headless;
+use chipvm::output::Vram;
+
+/// Renders the 64*32 low resolution screen as text, one line per row
+fn render(vram: &Vram) -> String {
+    vram.iter()
+        .take(32)
+        .map(|row| row.iter().take(64).map(|&p| if p != 0 { '#' } else { '.' }).collect::<String>() + "\n")
+        .collect()
+}
+
+fn check_golden(rom: &str, golden: &str, frames: usize) {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bytes = std::fs::read(dir.join(rom)).unwrap();
+    let screens = run_headless(&bytes, frames).unwrap();
+    let actual = render(screens.last().unwrap());
+
+    let golden_path = dir.join("tests/golden").join(golden);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&golden_path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&golden_path).unwrap();
+    assert!(actual == expected, "{} doesn't match {}:\n{}", rom, golden, actual);
+}
+
+#[test]
+fn opcodes() {
+    check_golden("tests/roms/opcodes.ch8", "opcodes.txt", 10);
+}
+
+#[test]
+fn connect4() {
+    check_golden("games/CONNECT4", "connect4.txt", 60);
+}
+
+#[test]
+fn tictac() {
+    check_golden("games/TICTAC", "tictac.txt", 60);
+}
@@ -0,0 +1,127 @@
+use chipvm::assembler::assemble;
+use chipvm::processor::EmuError;
+use chipvm::snapshot::SnapshotError;
+use chipvm::Processor;
+
+fn processor() -> Processor {
+    let rom = assemble("
+        loop:   ADD V0, 1
+                LD I, 0x300
+                LD [I], V0
+                LD F, V0
+                DRW V1, V1, 5
+                CALL sub
+                JP loop
+        sub:    LD DT, V0
+                RET
+    ").unwrap();
+
+    let mut processor = Processor::with_seed(1);
+    processor.load_program(&rom).unwrap();
+    processor
+}
+
+/// Offset of the stack pointer in a snapshot of a 4K processor: magic, version, memory size,
+/// memory, registers, stack size and stack come before it
+const SP_OFFSET: usize = 4 + 1 + 4 + 4096 + 16 + 4 + 16 * 4;
+
+#[test]
+fn restore_brings_back_the_snapshotted_state() {
+    let mut processor = processor();
+    for _ in 0..3 {
+        processor.tick([false; 16]);
+    }
+    let snapshot = processor.snapshot();
+    let (pc, i, sp, registers, vram) = (processor.pc(), processor.i(), processor.sp(), *processor.registers(), processor.vram);
+
+    for _ in 0..5 {
+        processor.tick([true; 16]);
+    }
+    processor.write_memory(0x400, 0x42).unwrap();
+    assert_ne!(processor.snapshot(), snapshot);
+
+    processor.restore(&snapshot).unwrap();
+
+    assert_eq!(processor.snapshot(), snapshot);
+    assert_eq!((processor.pc(), processor.i(), processor.sp()), (pc, i, sp));
+    assert_eq!(*processor.registers(), registers);
+    assert_eq!(processor.vram, vram);
+    assert_eq!(processor.read_memory(0x400), Some(0));
+}
+
+#[test]
+fn restore_rejects_impossible_states_without_touching_the_vm() {
+    let mut processor = processor();
+    processor.tick([false; 16]);
+    let good = processor.snapshot();
+    let len = good.len();
+    assert_eq!(good[SP_OFFSET..SP_OFFSET + 4], (processor.sp() as u32).to_le_bytes());
+    assert_eq!(good[len - 29], 1);
+
+    // The snapshot ends with planes, pc, I, the keypad, the wait flag, the waited for key,
+    // the pressed key and the halted flag
+    let corrupt = |offset: usize, bytes: &[u8]| {
+        let mut snapshot = good.clone();
+        snapshot[offset..offset + bytes.len()].copy_from_slice(bytes);
+        snapshot
+    };
+    let bad = [
+        corrupt(SP_OFFSET, &17u32.to_le_bytes()),
+        corrupt(len - 29, &[4]),
+        corrupt(len - 3, &[16]),
+        corrupt(len - 2, &[16]),
+    ];
+
+    processor.tick([false; 16]);
+    let before = processor.snapshot();
+    for snapshot in bad.iter() {
+        assert_eq!(processor.restore(snapshot), Err(SnapshotError::BadState));
+        assert_eq!(processor.snapshot(), before);
+    }
+
+    assert_eq!(processor.restore(&corrupt(len - 2, &[0xff])), Ok(()));
+}
+
+#[test]
+fn rewind_goes_back_to_an_earlier_frame() {
+    let mut processor = processor();
+    processor.enable_rewind(10);
+    for _ in 0..3 {
+        processor.tick([false; 16]);
+    }
+    let captured = processor.snapshot();
+
+    for _ in 0..4 {
+        processor.tick([false; 16]);
+    }
+    assert_eq!(processor.rewind(4), 4);
+
+    assert_eq!(processor.snapshot(), captured);
+}
+
+#[test]
+fn rewind_is_capped_by_the_buffer_size() {
+    let mut processor = processor();
+    processor.enable_rewind(2);
+    for _ in 0..5 {
+        processor.tick([false; 16]);
+    }
+
+    assert_eq!(processor.rewind(10), 2);
+    assert_eq!(processor.rewind(1), 0);
+}
+
+#[test]
+fn rewinding_past_an_error_clears_it() {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble("LD V1, 1\nRET").unwrap()).unwrap();
+    processor.set_speed(1);
+    processor.enable_rewind(10);
+
+    assert!(processor.try_tick([false; 16]).is_ok());
+    assert_eq!(processor.try_tick([false; 16]).err(), Some(EmuError::StackUnderflow));
+    assert_eq!(processor.rewind(2), 2);
+
+    assert!(processor.try_tick([false; 16]).is_ok());
+    assert_eq!((processor.register(1), processor.pc()), (1, 0x202));
+}
@@ -0,0 +1,55 @@
+use chipvm::assembler::assemble;
+use chipvm::Processor;
+
+/// Counts V0 down from 3 and then spins at `done`
+const COUNTDOWN: &str = "
+                LD V0, 3
+        loop:   ADD V0, 0xFF
+                SE V0, 0
+                JP loop
+        done:   JP done
+";
+
+#[test]
+fn stats_count_every_executed_opcode() {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble(COUNTDOWN).unwrap()).unwrap();
+    for _ in 0..9 {
+        processor.step();
+    }
+
+    let stats = processor.opcode_stats();
+    let mut counts: Vec<(&str, u64)> = stats.iter().map(|(&class, &count)| (class, count)).collect();
+    counts.sort();
+    assert_eq!(counts, [("1NNN", 2), ("3XKK", 3), ("6XKK", 1), ("7XKK", 3)]);
+}
+
+#[cfg(feature = "profiling")]
+#[test]
+fn profiler_spends_its_time_in_the_loop() {
+    // The setup runs once, the loop ten thousand times
+    let source = "
+                LD I, 0x300
+                LD V1, 10
+                LD V2, 20
+        loop:   DRW V1, V2, 15
+                ADD V3, 1
+                JP loop
+    ";
+    let mut processor = Processor::new();
+    processor.load_program(&assemble(source).unwrap()).unwrap();
+    for _ in 0..3 + 3 * 10_000 {
+        processor.step();
+    }
+
+    let report = processor.profile_report();
+    let total: std::time::Duration = report.iter().map(|&(_, time)| time).sum();
+    let in_loop: std::time::Duration = report
+        .iter()
+        .filter(|(class, _)| ["DXYN", "7XKK", "1NNN"].contains(class))
+        .map(|&(_, time)| time)
+        .sum();
+
+    assert!(in_loop > total * 9 / 10, "{:?}", report);
+    assert_eq!(report.len(), 5);
+}
@@ -0,0 +1,12 @@
+use chipvm::assembler::assemble;
+use chipvm::Processor;
+
+#[test]
+fn one_frame_sound_beeps_for_exactly_one_frame() {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble("LD V0, 1\nLD ST, V0\ndone: JP done").unwrap()).unwrap();
+
+    let beeps: Vec<bool> = (0..4).map(|_| processor.tick([false; 16]).beep).collect();
+
+    assert_eq!(beeps, [true, false, false, false]);
+}
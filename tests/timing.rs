@@ -0,0 +1,80 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chipvm::timing::{Clock, FrameTimer, IpsThrottle, RateCounter, Rates};
+
+/// A clock that only moves when the test moves it
+#[derive(Clone, Default)]
+struct FakeClock(Rc<Cell<Duration>>);
+
+impl FakeClock {
+    fn advance(&self, by: Duration) {
+        self.0.set(self.0.get() + by);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.0.get()
+    }
+}
+
+#[test]
+fn rates_update_once_per_second() {
+    let clock = FakeClock::default();
+    let mut counter = RateCounter::new(clock.clone());
+
+    for _ in 0..59 {
+        clock.advance(Duration::from_millis(1000 / 60));
+        assert_eq!(counter.frame(10), None);
+    }
+    clock.advance(Duration::from_millis(1000 - 59 * (1000 / 60)));
+
+    assert_eq!(counter.frame(10), Some(Rates { fps: 60, ips: 600 }));
+    assert_eq!(counter.rates(), Rates { fps: 60, ips: 600 });
+}
+
+#[test]
+fn rates_are_scaled_to_the_time_that_passed() {
+    let clock = FakeClock::default();
+    let mut counter = RateCounter::new(clock.clone());
+
+    for _ in 0..29 {
+        counter.frame(100);
+    }
+    clock.advance(Duration::from_secs(2));
+
+    assert_eq!(counter.frame(100), Some(Rates { fps: 15, ips: 1500 }));
+}
+
+/// Opcodes executed over a simulated second when each loop iteration takes the next of
+/// `render_ms` in turn
+fn opcodes_in_a_second(target_ips: u32, render_ms: &[u64]) -> usize {
+    let clock = FakeClock::default();
+    let mut timer = FrameTimer::new(clock.clone());
+    let mut throttle = IpsThrottle::new(target_ips);
+    let mut opcodes = 0;
+    let mut elapsed = 0;
+
+    for &ms in render_ms.iter().cycle() {
+        let ms = ms.min(1000 - elapsed);
+        clock.advance(Duration::from_millis(ms));
+        elapsed += ms;
+        for _ in 0..timer.frames_due() {
+            opcodes += throttle.cycles_for_frame();
+        }
+        if elapsed == 1000 {
+            return opcodes;
+        }
+    }
+
+    unreachable!()
+}
+
+#[test]
+fn opcode_count_is_independent_of_render_time() {
+    assert_eq!(opcodes_in_a_second(700, &[16]), 700);
+    assert_eq!(opcodes_in_a_second(700, &[5, 30, 12, 50, 1]), 700);
+    assert_eq!(opcodes_in_a_second(700, &[40]), 700);
+}
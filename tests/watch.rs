@@ -0,0 +1,58 @@
+use chipvm::assembler::assemble;
+use chipvm::watch::{Watch, WatchHit};
+use chipvm::Processor;
+
+fn processor(source: &str) -> Processor {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble(source).unwrap()).unwrap();
+    processor
+}
+
+#[test]
+fn register_watch_halts_after_the_change() {
+    let mut processor = processor("
+                LD V5, 3
+                LD V5, 3
+                LD V5, 9
+                LD V6, 1
+        done:   JP done
+    ");
+    processor.add_register_watch(5);
+
+    let state = processor.tick([false; 16]);
+    assert_eq!(state.hit_watch, Some(WatchHit { watch: Watch::Register(5), pc: 0x200, old: 0, new: 3 }));
+    assert_eq!(state.cycles, 1);
+
+    // Writing the same value again isn't a change
+    let state = processor.tick([false; 16]);
+    assert_eq!(state.hit_watch, Some(WatchHit { watch: Watch::Register(5), pc: 0x204, old: 3, new: 9 }));
+    assert_eq!(processor.pc(), 0x206);
+    assert_eq!(processor.register(6), 0);
+}
+
+#[test]
+fn memory_watch_sees_stores() {
+    let mut processor = processor("
+                LD I, 0x300
+                LD V0, 0x11
+                LD V1, 0x22
+                LD [I], V1
+        done:   JP done
+    ");
+    processor.add_memory_watch(0x301);
+
+    let state = processor.tick([false; 16]);
+    assert_eq!(state.hit_watch, Some(WatchHit { watch: Watch::Memory(0x301), pc: 0x206, old: 0, new: 0x22 }));
+}
+
+#[test]
+fn removed_watch_is_ignored() {
+    let mut processor = processor("
+                LD V5, 3
+        done:   JP done
+    ");
+    processor.add_register_watch(5);
+    processor.remove_watch(Watch::Register(5));
+
+    assert_eq!(processor.tick([false; 16]).hit_watch, None);
+}
@@ -0,0 +1,55 @@
+use chipvm::assembler::assemble;
+use chipvm::Processor;
+
+fn processor(source: &str, watchdog: Option<usize>) -> Processor {
+    let mut processor = Processor::new();
+    processor.load_program(&assemble(source).unwrap()).unwrap();
+    processor.set_watchdog(watchdog);
+
+    processor
+}
+
+#[test]
+fn a_jump_to_itself_is_reported_after_the_threshold() {
+    let mut processor = processor("LD V0, 1\nloop: JP loop", Some(3));
+
+    // The first tick gets from 0x200 to the loop, so it made progress
+    assert!(!processor.tick([false; 16]).stuck);
+    assert!(!processor.tick([false; 16]).stuck);
+    assert!(!processor.tick([false; 16]).stuck);
+    assert!(processor.tick([false; 16]).stuck);
+
+    // Reporting it doesn't stop the vm
+    let state = processor.tick([false; 16]);
+    assert!(state.stuck);
+    assert_eq!(state.error, None);
+    assert_eq!(state.cycles, 10);
+    assert_eq!(processor.pc(), 0x202);
+}
+
+#[test]
+fn nothing_is_reported_without_a_watchdog() {
+    let mut processor = processor("loop: JP loop", None);
+
+    for _ in 0..100 {
+        assert!(!processor.tick([false; 16]).stuck);
+    }
+}
+
+#[test]
+fn loops_that_move_pc_are_not_reported() {
+    let mut processor = processor("loop: ADD V0, 1\nJP loop", Some(1));
+
+    for _ in 0..10 {
+        assert!(!processor.tick([false; 16]).stuck);
+    }
+}
+
+#[test]
+fn waiting_for_a_key_is_not_reported() {
+    let mut processor = processor("LD V0, K", Some(1));
+
+    for _ in 0..10 {
+        assert!(!processor.tick([false; 16]).stuck);
+    }
+}
@@ -0,0 +1,74 @@
+use chipvm::assembler::assemble;
+use chipvm::processor::XO_CHIP_MEMORY_SIZE;
+use chipvm::Processor;
+
+fn run(source: &str) -> Processor {
+    let mut processor = Processor::with_memory_size(XO_CHIP_MEMORY_SIZE);
+    processor.load_program(&assemble(source).unwrap()).unwrap();
+    processor.write_memory(0xBEEF, 0x42).unwrap();
+
+    processor.tick([false; 16]);
+    processor
+}
+
+#[test]
+fn long_load_reaches_past_4k() {
+    let processor = run("
+                DW 0xF000
+                DW 0xBEEF
+                LD V0, [I]
+        done:   JP done
+    ");
+
+    assert_eq!(processor.i(), 0xBEEF + 1);
+    assert_eq!(processor.register(0), 0x42);
+}
+
+#[test]
+fn skips_jump_over_the_whole_long_load() {
+    let processor = run("
+                SE V0, 0
+                DW 0xF000
+                DW 0xBEEF
+                LD V1, 1
+        done:   JP done
+    ");
+
+    assert_eq!(processor.i(), 0);
+    assert_eq!(processor.register(1), 1);
+}
+
+/// Draws the digit 8 in high resolution into both planes, then runs `clear` with `planes`
+/// selected
+fn draw_and_clear(planes: u8) -> chipvm::output::Vram {
+    let mut processor = run(&format!("
+                HIGH
+                PLANE 3
+                LD V0, 8
+                LD F, V0
+                DRW V0, V0, 5
+                LD V0, 100
+                DRW V0, V0, 5
+                PLANE {}
+                CLS
+        done:   JP done
+    ", planes));
+    processor.tick([false; 16]);
+
+    processor.vram
+}
+
+#[test]
+fn clear_zeroes_every_selected_plane_in_high_resolution() {
+    let vram = draw_and_clear(3);
+
+    assert!(vram.iter().flatten().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn clear_keeps_planes_that_arent_selected() {
+    let vram = draw_and_clear(1);
+
+    assert!(vram.iter().flatten().all(|&pixel| pixel & 1 == 0));
+    assert!(vram.iter().flatten().any(|&pixel| pixel == 2));
+}